@@ -0,0 +1,143 @@
+/*!
+Derive macros for the `IntoDifferentialForm` and `IntoRealForm` traits of the `differential` crate.
+
+`#[derive(IntoDifferentialForm)]` on a struct `Params` generates a struct `ParamsDifferential`
+with the same fields converted into their differential form, together with the conversion.
+`#[derive(IntoRealForm)]` generates the conversion back from `ParamsDifferential` to `Params`.
+Fields are converted one by one with the traits, so nested structs deriving the traits work too.
+
+The name of the generated struct can be changed with `#[differential(name = "...")]`.
+*/
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote};
+use syn::{parse_macro_input, Data, DeriveInput, Fields, Ident, LitStr};
+
+/// Generates the differential version of a struct and implements `IntoDifferentialForm` for it
+#[proc_macro_derive(IntoDifferentialForm, attributes(differential))]
+pub fn derive_into_differential_form(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand_into_differential_form(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Implements `IntoRealForm` for the differential version of a struct
+///
+/// The differential version is the one generated by `#[derive(IntoDifferentialForm)]`.
+#[proc_macro_derive(IntoRealForm, attributes(differential))]
+pub fn derive_into_real_form(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand_into_real_form(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Checks that the input is a non-generic struct and returns its fields
+fn struct_fields(input: &DeriveInput) -> syn::Result<&Fields> {
+    if !input.generics.params.is_empty() {
+        return Err(syn::Error::new_spanned(&input.generics, "generic structs are not supported"));
+    }
+    match &input.data {
+        Data::Struct(data) => Ok(&data.fields),
+        _ => Err(syn::Error::new_spanned(&input.ident, "only structs are supported")),
+    }
+}
+
+/// The name of the generated differential struct
+fn differential_name(input: &DeriveInput) -> syn::Result<Ident> {
+    let mut name = format_ident!("{}Differential", input.ident);
+    for attr in input.attrs.iter().filter(|attr| attr.path().is_ident("differential")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("name") {
+                let value: LitStr = meta.value()?.parse()?;
+                name = value.parse()?;
+                Ok(())
+            } else {
+                Err(meta.error("unsupported differential attribute"))
+            }
+        })?;
+    }
+    Ok(name)
+}
+
+/// Builds `Target { a: convert(source.a), .. }` for the fields of a struct
+fn convert_fields(fields: &Fields, target: &Ident, convert: &TokenStream2) -> TokenStream2 {
+    match fields {
+        Fields::Named(fields) => {
+            let conversions = fields.named.iter().map(|field| {
+                let name = &field.ident;
+                quote!(#name: #convert(self.#name))
+            });
+            quote!(#target { #(#conversions),* })
+        }
+        Fields::Unnamed(fields) => {
+            let conversions = (0..fields.unnamed.len()).map(|i| {
+                let index = syn::Index::from(i);
+                quote!(#convert(self.#index))
+            });
+            quote!(#target(#(#conversions),*))
+        }
+        Fields::Unit => quote!(#target),
+    }
+}
+
+fn expand_into_differential_form(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let fields = struct_fields(input)?;
+    let name = &input.ident;
+    let vis = &input.vis;
+    let differential = differential_name(input)?;
+
+    let differential_fields = fields.iter().map(|field| {
+        let vis = &field.vis;
+        let ty = &field.ty;
+        let ty = quote!(<#ty as ::differential::IntoDifferentialForm>::DifferentialForm);
+        match &field.ident {
+            Some(ident) => quote!(#vis #ident: #ty),
+            None => quote!(#vis #ty),
+        }
+    });
+    let definition = match fields {
+        Fields::Named(_) => quote!(#vis struct #differential { #(#differential_fields),* }),
+        Fields::Unnamed(_) => quote!(#vis struct #differential(#(#differential_fields),*);),
+        Fields::Unit => quote!(#vis struct #differential;),
+    };
+    let conversion = convert_fields(
+        fields,
+        &differential,
+        &quote!(::differential::IntoDifferentialForm::into_differential_form),
+    );
+
+    let doc = format!("Differential form of [`{}`]", name);
+    Ok(quote! {
+        #[doc = #doc]
+        #[derive(Debug, Clone)]
+        #definition
+
+        impl ::differential::IntoDifferentialForm for #name {
+            type DifferentialForm = #differential;
+
+            fn into_differential_form(self) -> Self::DifferentialForm {
+                #conversion
+            }
+        }
+    })
+}
+
+fn expand_into_real_form(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let fields = struct_fields(input)?;
+    let name = &input.ident;
+    let differential = differential_name(input)?;
+    let conversion = convert_fields(fields, name, &quote!(::differential::IntoRealForm::into_real_form));
+
+    Ok(quote! {
+        impl ::differential::IntoRealForm for #differential {
+            type RealForm = #name;
+
+            fn into_real_form(self) -> Self::RealForm {
+                #conversion
+            }
+        }
+    })
+}
@@ -0,0 +1,223 @@
+/*!
+Finite-difference approximations of Jacobian matrices.
+
+These are meant for functions that cannot be evaluated on [`Differential`](crate::Differential)
+values (e.g. calls into opaque black-box code). The result has the same layout as
+[`jacobian`](crate::jacobian), so the two can be swapped freely.
+*/
+
+use nalgebra::{DMatrix, DVector};
+
+/// The finite-difference stencil used to approximate a derivative
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Stencil {
+    /// `(f(x + h) - f(x)) / h`, error `O(h)`
+    Forward,
+
+    /// `(f(x + h) - f(x - h)) / 2h`, error `O(h^2)`
+    #[default]
+    Central,
+
+    /// `(-f(x + 2h) + 8f(x + h) - 8f(x - h) + f(x - 2h)) / 12h`, error `O(h^4)`
+    FivePoint,
+}
+
+impl Stencil {
+    /// The order of the leading truncation error term
+    pub fn order(self) -> i32 {
+        match self {
+            Stencil::Forward => 1,
+            Stencil::Central => 2,
+            Stencil::FivePoint => 4,
+        }
+    }
+
+    /// The distance between the orders of successive truncation error terms
+    fn order_step(self) -> i32 {
+        match self {
+            Stencil::Forward => 1,
+            Stencil::Central | Stencil::FivePoint => 2,
+        }
+    }
+
+    /// Approximates the derivative of `f` along the `j`-th parameter with step `h`
+    fn derivative(self, f: &impl Fn(&[f64]) -> Vec<f64>, params: &[f64], f0: &[f64], j: usize, h: f64) -> Vec<f64> {
+        let mut x = params.to_vec();
+        let mut eval = |offset: f64| {
+            x[j] = params[j] + offset;
+            f(&x)
+        };
+        match self {
+            Stencil::Forward => {
+                let f1 = eval(h);
+                f1.iter().zip(f0).map(|(f1, f0)| (f1 - f0) / h).collect()
+            }
+            Stencil::Central => {
+                let f1 = eval(h);
+                let f_1 = eval(-h);
+                f1.iter().zip(&f_1).map(|(f1, f_1)| (f1 - f_1) / (2.0 * h)).collect()
+            }
+            Stencil::FivePoint => {
+                let f2 = eval(2.0 * h);
+                let f1 = eval(h);
+                let f_1 = eval(-h);
+                let f_2 = eval(-2.0 * h);
+                (0..f0.len())
+                    .map(|i| (-f2[i] + 8.0 * f1[i] - 8.0 * f_1[i] + f_2[i]) / (12.0 * h))
+                    .collect()
+            }
+        }
+    }
+}
+
+/// Options for [`finite_difference_jacobian`]
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct FiniteDifferenceOptions {
+    /// The stencil to use
+    pub stencil: Stencil,
+
+    /// The relative noise level of the function values.
+    ///
+    /// If `None`, it is estimated with [`estimate_noise`].
+    pub noise: Option<f64>,
+
+    /// The number of Richardson extrapolation levels, `0` disables the extrapolation
+    pub richardson_levels: usize,
+}
+
+/// Estimates the relative noise level of `f` around `params`
+///
+/// The estimate is obtained from the sixth order differences of `f` sampled along a line
+/// through `params` (Hamming's method). The result is never smaller than the machine epsilon.
+pub fn estimate_noise(f: impl Fn(&[f64]) -> Vec<f64>, params: &[f64]) -> f64 {
+    const ORDER: usize = 6;
+    const SAMPLES: usize = 9;
+    const SPACING: f64 = 1e-3;
+
+    // gamma_k = (k!)^2 / (2k)!
+    let gamma = (1..=ORDER).fold(1.0, |acc, k| acc * k as f64 / (ORDER + k) as f64);
+
+    let samples: Vec<Vec<f64>> = (0..SAMPLES)
+        .map(|k| {
+            let t = (k as f64 - (SAMPLES / 2) as f64) * SPACING;
+            let x: Vec<f64> = params.iter().map(|x| x + t * x.abs().max(1.0)).collect();
+            f(&x)
+        })
+        .collect();
+
+    let center = &samples[SAMPLES / 2];
+    let mut noise = f64::EPSILON;
+    for i in 0..center.len() {
+        let mut differences: Vec<f64> = samples.iter().map(|s| s[i]).collect();
+        for _ in 0..ORDER {
+            differences = differences.windows(2).map(|w| w[1] - w[0]).collect();
+        }
+        let mean_square = differences.iter().map(|d| d * d).sum::<f64>() / differences.len() as f64;
+        let sigma = (gamma * mean_square).sqrt();
+        if center[i] != 0.0 {
+            noise = noise.max(sigma / center[i].abs());
+        }
+    }
+    noise
+}
+
+/// Computes the per-parameter steps for the given stencil and relative noise level
+///
+/// The step balances the truncation error of the stencil against the rounding/noise error
+/// and is scaled by the magnitude of each parameter.
+pub fn step_sizes(params: &[f64], stencil: Stencil, noise: f64) -> Vec<f64> {
+    let base = noise.max(f64::EPSILON).powf(1.0 / (stencil.order() + 1) as f64);
+    params
+        .iter()
+        .map(|x| {
+            let h = base * x.abs().max(1.0);
+            // make sure that x + h - x is exactly representable
+            (x + h) - x
+        })
+        .collect()
+}
+
+/// Computes the Jacobian matrix of a function f: R^n -> R^m with finite differences
+///
+/// The result has the same layout as [`jacobian`](crate::jacobian): `m` rows and `n` columns.
+pub fn finite_difference_jacobian(f: impl Fn(&[f64]) -> Vec<f64>, params: &[f64], options: &FiniteDifferenceOptions) -> DMatrix<f64> {
+    let stencil = options.stencil;
+    let noise = options.noise.unwrap_or_else(|| estimate_noise(&f, params));
+    let steps = step_sizes(params, stencil, noise);
+    let f0 = f(params);
+
+    let mut jacobian = DMatrix::zeros(f0.len(), params.len());
+    for (j, &step) in steps.iter().enumerate() {
+        // start from a larger step so that the finest step is the optimal one
+        let levels = options.richardson_levels;
+        let mut tableau: Vec<Vec<f64>> = (0..=levels)
+            .map(|m| {
+                let h = step * 2f64.powi((levels - m) as i32);
+                stencil.derivative(&f, params, &f0, j, h)
+            })
+            .collect();
+
+        for k in 1..=levels {
+            let factor = 2f64.powi(stencil.order() + stencil.order_step() * (k as i32 - 1)) - 1.0;
+            for m in (k..=levels).rev() {
+                let (coarse, fine) = tableau.split_at_mut(m);
+                for (fine, coarse) in fine[0].iter_mut().zip(&coarse[m - 1]) {
+                    *fine += (*fine - coarse) / factor;
+                }
+            }
+        }
+
+        jacobian.set_column(j, &DVector::from_vec(tableau.swap_remove(levels)));
+    }
+    jacobian
+}
+
+#[cfg(test)]
+mod tests {
+    use num_traits::real::Real;
+
+    use crate::{jacobian, Differential};
+
+    use super::*;
+
+    fn model<T: Real>(x: &[T]) -> Vec<T> {
+        vec![x[0].sin() * x[1], (x[0] * x[1]).exp(), x[1].powi(3)]
+    }
+
+    fn max_error(a: &DMatrix<f64>, b: &DMatrix<f64>) -> f64 {
+        (a - b).abs().max()
+    }
+
+    #[test]
+    fn stencils_match_jacobian() {
+        let params = [0.3, 0.7];
+        let exact = jacobian(model::<Differential>, &params);
+        for (stencil, tolerance) in [(Stencil::Forward, 1e-6), (Stencil::Central, 1e-9), (Stencil::FivePoint, 1e-11)] {
+            let options = FiniteDifferenceOptions { stencil, ..Default::default() };
+            let approx = finite_difference_jacobian(model::<f64>, &params, &options);
+            assert!(max_error(&exact, &approx) < tolerance, "{:?}", stencil);
+        }
+    }
+
+    #[test]
+    fn richardson_improves_forward_differences() {
+        let params = [0.3, 0.7];
+        let exact = jacobian(model::<Differential>, &params);
+        let plain = FiniteDifferenceOptions { stencil: Stencil::Forward, noise: Some(1e-10), richardson_levels: 0 };
+        let extrapolated = FiniteDifferenceOptions { richardson_levels: 2, ..plain.clone() };
+        let plain = finite_difference_jacobian(model::<f64>, &params, &plain);
+        let extrapolated = finite_difference_jacobian(model::<f64>, &params, &extrapolated);
+        assert!(max_error(&exact, &extrapolated) < max_error(&exact, &plain) / 10.0);
+    }
+
+    #[test]
+    fn noise_estimate() {
+        let smooth = |x: &[f64]| vec![x[0].exp()];
+        assert!(estimate_noise(smooth, &[1.0]) < 1e-14);
+
+        // deterministic pseudo-random noise of relative size ~1e-8
+        let noisy = |x: &[f64]| vec![x[0].exp() * (1.0 + 1e-8 * ((x[0] * 1e7).sin() * 43758.5453).fract())];
+        let noise = estimate_noise(noisy, &[1.0]);
+        assert!(noise > 1e-10 && noise < 1e-6, "{}", noise);
+    }
+}
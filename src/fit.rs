@@ -0,0 +1,450 @@
+/*!
+Nonlinear least-squares fitting.
+
+The residuals are written against [`Differential`], so the Jacobian is computed exactly by forward
+differentiation instead of finite differences. A typical residual for data `(xᵢ, yᵢ ± σᵢ)` is
+`(model(xᵢ, p) - yᵢ) / σᵢ`. Data with outliers can be fitted with a robust [`Loss`] through [`irls`].
+*/
+
+use nalgebra::{DMatrix, DVector};
+use num_traits::Float;
+
+use crate::{Differential, JacobianEvaluation};
+
+mod robust;
+pub use robust::{irls, IrlsOptions, Loss, RobustFitReport};
+
+/// Options of [`levenberg_marquardt`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LevenbergMarquardtOptions {
+    /// Stop when an accepted step reduces χ² by at most this fraction
+    pub chi_squared_tolerance: f64,
+
+    /// Stop when a step is at most `parameter_tolerance * (‖p‖ + parameter_tolerance)`
+    pub parameter_tolerance: f64,
+
+    /// Stop when the largest component of the gradient `Jᵀr` is at most this
+    pub gradient_tolerance: f64,
+
+    /// Maximum number of iterations (Jacobian evaluations)
+    pub max_iterations: usize,
+
+    /// The initial damping parameter λ
+    pub initial_damping: f64,
+}
+
+impl Default for LevenbergMarquardtOptions {
+    fn default() -> Self {
+        Self {
+            chi_squared_tolerance: 1e-12,
+            parameter_tolerance: 1e-12,
+            gradient_tolerance: 1e-12,
+            max_iterations: 200,
+            initial_damping: 1e-3,
+        }
+    }
+}
+
+/// Why a fit stopped
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FitStopReason {
+    /// The relative reduction of χ² reached its tolerance
+    ChiSquaredTolerance,
+
+    /// The step reached the parameter tolerance
+    ParameterTolerance,
+
+    /// The gradient reached its tolerance
+    GradientTolerance,
+
+    /// No step reducing χ² was found, even with maximal damping
+    Stagnation,
+
+    /// The maximum number of iterations was reached
+    MaxIterations,
+
+    /// The residuals are not finite
+    NonFinite,
+}
+
+/// The result of a fit
+#[derive(Debug, Clone, PartialEq)]
+pub struct FitReport {
+    /// The fitted parameters
+    pub params: Vec<f64>,
+
+    /// The residuals at the fitted parameters
+    pub residuals: Vec<f64>,
+
+    /// The sum of the squared residuals
+    pub chi_squared: f64,
+
+    /// The number of residuals minus the number of parameters
+    pub degrees_of_freedom: usize,
+
+    /// `χ² / degrees_of_freedom`, NaN without degrees of freedom
+    pub reduced_chi_squared: f64,
+
+    /// The covariance estimate `(JᵀJ)⁻¹ σ²` with `σ²` the reduced χ², `None` if `JᵀJ` is singular
+    pub covariance: Option<DMatrix<f64>>,
+
+    /// The standard uncertainties of the parameters, the square roots of the covariance diagonal
+    pub uncertainties: Option<Vec<f64>>,
+
+    /// The number of iterations
+    pub iterations: usize,
+
+    /// Why the fit stopped
+    pub reason: FitStopReason,
+}
+
+impl FitReport {
+    /// Returns `true` if the fit stopped on a tolerance
+    pub fn converged(&self) -> bool {
+        matches!(
+            self.reason,
+            FitStopReason::ChiSquaredTolerance | FitStopReason::ParameterTolerance | FitStopReason::GradientTolerance
+        )
+    }
+}
+
+/// The largest damping before giving up
+const MAX_DAMPING: f64 = 1e16;
+
+/// Fits the parameters minimizing `Σ rᵢ(p)²` with the Levenberg–Marquardt method
+///
+/// Each iteration solves `(JᵀJ + λ diag(JᵀJ)) δ = -Jᵀr`, decreasing λ after steps that reduce χ²
+/// and increasing it otherwise.
+pub fn levenberg_marquardt(
+    residuals: impl Fn(&[Differential]) -> Vec<Differential>,
+    params: &[f64],
+    options: &LevenbergMarquardtOptions,
+) -> FitReport {
+    let mut evaluation = JacobianEvaluation::new(&residuals, params);
+    let mut chi_squared = sum_of_squares(&evaluation.outputs);
+    let mut damping = options.initial_damping;
+    let mut iterations = 0;
+
+    let reason = loop {
+        if !chi_squared.is_finite() {
+            break FitStopReason::NonFinite;
+        }
+        let j = &evaluation.jacobian;
+        let r = DVector::from_column_slice(&evaluation.outputs);
+        let gradient = j.tr_mul(&r);
+        if gradient.amax() <= options.gradient_tolerance {
+            break FitStopReason::GradientTolerance;
+        }
+        if iterations >= options.max_iterations {
+            break FitStopReason::MaxIterations;
+        }
+        iterations += 1;
+
+        let normal = j.tr_mul(j);
+        let p = DVector::from_column_slice(&evaluation.params);
+        let accepted = loop {
+            if damping > MAX_DAMPING {
+                break None;
+            }
+            let mut damped = normal.clone();
+            for i in 0..damped.nrows() {
+                damped[(i, i)] += damping * normal[(i, i)].max(f64::EPSILON);
+            }
+            let Some(step) = damped.cholesky().map(|c| -c.solve(&gradient)) else {
+                damping *= 10.0;
+                continue;
+            };
+            let candidate = &p + &step;
+            let candidate_residuals = values(&residuals, candidate.as_slice());
+            let candidate_chi_squared = sum_of_squares(&candidate_residuals);
+            if candidate_chi_squared.is_finite() && candidate_chi_squared < chi_squared {
+                damping = (damping * 0.1).max(f64::EPSILON);
+                break Some((candidate, step, candidate_chi_squared));
+            }
+            damping *= 10.0;
+        };
+        let Some((candidate, step, candidate_chi_squared)) = accepted else {
+            break FitStopReason::Stagnation;
+        };
+
+        let reduction = (chi_squared - candidate_chi_squared) / chi_squared;
+        evaluation = JacobianEvaluation::new(&residuals, candidate.as_slice());
+        chi_squared = candidate_chi_squared;
+        if step.norm() <= options.parameter_tolerance * (candidate.norm() + options.parameter_tolerance) {
+            break FitStopReason::ParameterTolerance;
+        }
+        if reduction <= options.chi_squared_tolerance {
+            break FitStopReason::ChiSquaredTolerance;
+        }
+    };
+
+    FitReport::new(evaluation, chi_squared, iterations, reason)
+}
+
+impl FitReport {
+    /// Computes the statistics of a fit from the final evaluation
+    fn new(evaluation: JacobianEvaluation, chi_squared: f64, iterations: usize, reason: FitStopReason) -> Self {
+        let degrees_of_freedom = evaluation.outputs.len().saturating_sub(evaluation.params.len());
+        let reduced_chi_squared = if degrees_of_freedom > 0 {
+            chi_squared / degrees_of_freedom as f64
+        } else {
+            f64::NAN
+        };
+        let covariance = evaluation
+            .jacobian
+            .tr_mul(&evaluation.jacobian)
+            .try_inverse()
+            .map(|inverse| inverse * reduced_chi_squared);
+        let uncertainties = covariance.as_ref().map(|c| c.diagonal().iter().map(|v| v.sqrt()).collect());
+        Self {
+            params: evaluation.params,
+            residuals: evaluation.outputs,
+            chi_squared,
+            degrees_of_freedom,
+            reduced_chi_squared,
+            covariance,
+            uncertainties,
+            iterations,
+            reason,
+        }
+    }
+}
+
+/// A constraint on a fitted parameter
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum ParameterConstraint {
+    /// The parameter is unconstrained
+    #[default]
+    Free,
+
+    /// The parameter keeps its initial value
+    Frozen,
+
+    /// The parameter stays within `lower..upper`, either of which may be infinite
+    Bounded {
+        /// The lower bound
+        lower: f64,
+
+        /// The upper bound
+        upper: f64,
+    },
+}
+
+impl ParameterConstraint {
+    /// A parameter that stays strictly positive, like a mass or a rate
+    pub const POSITIVE: Self = Self::Bounded { lower: 0.0, upper: f64::INFINITY };
+
+    /// Relative distance from a bound at which initial values on (or beyond) the bound are placed
+    const INTERIOR: f64 = 1e-8;
+
+    /// Maps the parameter to the unconstrained internal parameter
+    fn to_internal(self, p: f64) -> f64 {
+        match self {
+            Self::Free | Self::Frozen => p,
+            Self::Bounded { lower, upper } => match (lower.is_finite(), upper.is_finite()) {
+                (true, true) => {
+                    let t = ((p - lower) / (upper - lower)).clamp(Self::INTERIOR, 1.0 - Self::INTERIOR);
+                    (t / (1.0 - t)).ln()
+                }
+                (true, false) => (p - lower).max(Self::INTERIOR * (1.0 + lower.abs())).ln(),
+                (false, true) => (upper - p).max(Self::INTERIOR * (1.0 + upper.abs())).ln(),
+                (false, false) => p,
+            },
+        }
+    }
+
+    /// Maps the internal parameter back, the inverse of `to_internal`
+    ///
+    /// Two-sided bounds use a sigmoid, one-sided bounds an exponential.
+    fn to_external(self, u: Differential) -> Differential {
+        match self {
+            Self::Free | Self::Frozen => u,
+            Self::Bounded { lower, upper } => match (lower.is_finite(), upper.is_finite()) {
+                (true, true) => {
+                    let sigmoid = Differential::from(1.0) / (Differential::from(1.0) + (-u).exp());
+                    sigmoid * (upper - lower) + Differential::from(lower)
+                }
+                (true, false) => u.exp() + Differential::from(lower),
+                (false, true) => Differential::from(upper) - u.exp(),
+                (false, false) => u,
+            },
+        }
+    }
+}
+
+/// Fits the parameters with [`levenberg_marquardt`], subject to bounds and frozen parameters
+///
+/// Bounded parameters are fitted through a smooth reparameterization, whose chain rule is handled
+/// by the differentials. The covariance and the uncertainties in the report refer to the original
+/// parameters, with zero uncertainty for the frozen ones. Note that they describe the unconstrained
+/// local curvature, which may be inaccurate for a parameter ending up at a bound.
+///
+/// # Panics
+///
+/// If `constraints` does not have one entry per parameter or a bound is empty (`lower >= upper`).
+pub fn levenberg_marquardt_constrained(
+    residuals: impl Fn(&[Differential]) -> Vec<Differential>,
+    params: &[f64],
+    constraints: &[ParameterConstraint],
+    options: &LevenbergMarquardtOptions,
+) -> FitReport {
+    assert_eq!(params.len(), constraints.len(), "every parameter needs a constraint");
+    for constraint in constraints {
+        if let ParameterConstraint::Bounded { lower, upper } = constraint {
+            assert!(lower < upper, "the bounds of a parameter must not be empty");
+        }
+    }
+    let free: Vec<usize> = (0..params.len()).filter(|&i| constraints[i] != ParameterConstraint::Frozen).collect();
+
+    // the full parameters, with the free ones replaced by the mapped `free_params`
+    let expand = |free_params: &[Differential], map: &dyn Fn(usize, Differential) -> Differential| {
+        let mut full: Vec<Differential> = params.iter().map(|&p| p.into()).collect();
+        for (&i, &q) in free.iter().zip(free_params) {
+            full[i] = map(i, q);
+        }
+        full
+    };
+
+    let internal: Vec<f64> = free.iter().map(|&i| constraints[i].to_internal(params[i])).collect();
+    let inner = levenberg_marquardt(
+        |u| residuals(&expand(u, &|i, u| constraints[i].to_external(u))),
+        &internal,
+        options,
+    );
+
+    let fitted: Vec<f64> = free
+        .iter()
+        .zip(&inner.params)
+        .map(|(&i, &u)| constraints[i].to_external(u.into()).value)
+        .collect();
+    let evaluation = JacobianEvaluation::new(|q: &[Differential]| residuals(&expand(q, &|_, q| q)), &fitted);
+    let report = FitReport::new(evaluation, inner.chi_squared, inner.iterations, inner.reason);
+
+    let n = params.len();
+    let mut full_params = params.to_vec();
+    for (&i, &p) in free.iter().zip(&report.params) {
+        full_params[i] = p;
+    }
+    let covariance = report.covariance.map(|c| {
+        let mut full = DMatrix::zeros(n, n);
+        for (a, &i) in free.iter().enumerate() {
+            for (b, &j) in free.iter().enumerate() {
+                full[(i, j)] = c[(a, b)];
+            }
+        }
+        full
+    });
+    let uncertainties = covariance.as_ref().map(|c| c.diagonal().iter().map(|v| v.sqrt()).collect());
+    FitReport {
+        params: full_params,
+        covariance,
+        uncertainties,
+        ..report
+    }
+}
+
+/// The values of the residuals at `params`
+fn values(residuals: &impl Fn(&[Differential]) -> Vec<Differential>, params: &[f64]) -> Vec<f64> {
+    let params: Vec<Differential> = params.iter().map(|x| (*x).into()).collect();
+    residuals(&params).iter().map(|r| r.value).collect()
+}
+
+fn sum_of_squares(xs: &[f64]) -> f64 {
+    xs.iter().map(|x| x * x).sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const XS: [f64; 8] = [0.0, 0.5, 1.0, 1.5, 2.0, 2.5, 3.0, 3.5];
+
+    /// `2.5 exp(-1.3 x)` with a fixed pattern of noise
+    fn data() -> Vec<f64> {
+        let noise = [0.01, -0.02, 0.015, 0.0, -0.01, 0.02, -0.015, 0.005];
+        XS.iter().zip(noise).map(|(x, e)| 2.5 * (-1.3 * x).exp() + e).collect()
+    }
+
+    #[test]
+    fn fits_exponential_decay() {
+        let ys = data();
+        let residuals = |p: &[Differential]| {
+            XS.iter().zip(&ys).map(|(&x, &y)| p[0] * (p[1] * -x).exp() - Differential::from(y)).collect()
+        };
+        let report = levenberg_marquardt(residuals, &[1.0, 0.5], &Default::default());
+        assert!(report.converged());
+        assert!((report.params[0] - 2.5).abs() < 0.05);
+        assert!((report.params[1] - 1.3).abs() < 0.05);
+        assert_eq!(report.degrees_of_freedom, 6);
+        assert!((report.reduced_chi_squared - report.chi_squared / 6.0).abs() < 1e-15);
+
+        let covariance = report.covariance.unwrap();
+        let uncertainties = report.uncertainties.unwrap();
+        assert!((uncertainties[1] - covariance[(1, 1)].sqrt()).abs() < 1e-15);
+        assert!(uncertainties.iter().all(|&u| u > 0.0 && u < 0.1));
+        // the amplitude and the rate of a decay are positively correlated
+        assert!(covariance[(0, 1)] > 0.0);
+    }
+
+    #[test]
+    fn linear_fit_matches_closed_form() {
+        // y = a + b x fitted to (0, 1), (1, 3), (2, 4)
+        let points = [(0.0, 1.0), (1.0, 3.0), (2.0, 4.0)];
+        let residuals = |p: &[Differential]| points.iter().map(|&(x, y)| p[0] + p[1] * x - Differential::from(y)).collect();
+        let report = levenberg_marquardt(residuals, &[0.0, 0.0], &Default::default());
+        assert!((report.params[0] - 7.0 / 6.0).abs() < 1e-9);
+        assert!((report.params[1] - 1.5).abs() < 1e-9);
+        // χ² = 1/6 with one degree of freedom, var(b) = σ² / Σ(x - x̄)² = (1/6) / 2
+        assert!((report.chi_squared - 1.0 / 6.0).abs() < 1e-9);
+        assert!((report.covariance.unwrap()[(1, 1)] - 1.0 / 12.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn unidentifiable_parameters_have_no_covariance() {
+        // only the sum of the parameters is determined
+        let residuals = |p: &[Differential]| vec![p[0] + p[1] - Differential::from(1.0), p[0] + p[1] - Differential::from(1.2)];
+        let report = levenberg_marquardt(residuals, &[0.0, 0.0], &Default::default());
+        assert!((report.params[0] + report.params[1] - 1.1).abs() < 1e-9);
+        assert!(report.covariance.is_none() && report.uncertainties.is_none());
+    }
+
+    #[test]
+    fn bounds_and_frozen_parameters() {
+        let ys = data();
+        let residuals = |p: &[Differential]| {
+            XS.iter().zip(&ys).map(|(&x, &y)| p[0] * (p[1] * -x).exp() + p[2] - Differential::from(y)).collect()
+        };
+        let constraints = [
+            ParameterConstraint::Bounded { lower: 0.0, upper: 2.0 },
+            ParameterConstraint::POSITIVE,
+            ParameterConstraint::Frozen,
+        ];
+        let report = levenberg_marquardt_constrained(residuals, &[1.0, 5.0, 0.0], &constraints, &Default::default());
+        assert!(report.converged());
+        // the amplitude is pushed against its upper bound, the rate stays positive
+        assert!(report.params[0] <= 2.0 && report.params[0] > 1.99);
+        assert!(report.params[1] > 0.0);
+        assert_eq!(report.params[2], 0.0);
+        assert_eq!(report.degrees_of_freedom, 6);
+
+        let uncertainties = report.uncertainties.unwrap();
+        assert_eq!(uncertainties.len(), 3);
+        assert_eq!(uncertainties[2], 0.0);
+    }
+
+    #[test]
+    fn constrained_covariance_refers_to_original_parameters() {
+        let ys = data();
+        let residuals = |p: &[Differential]| {
+            XS.iter().zip(&ys).map(|(&x, &y)| p[0] * (p[1] * -x).exp() - Differential::from(y)).collect()
+        };
+        let free = levenberg_marquardt(residuals, &[1.0, 0.5], &Default::default());
+        let constraints = [ParameterConstraint::POSITIVE, ParameterConstraint::Bounded { lower: 0.0, upper: 10.0 }];
+        let bounded = levenberg_marquardt_constrained(residuals, &[1.0, 0.5], &constraints, &Default::default());
+        for i in 0..2 {
+            assert!((free.params[i] - bounded.params[i]).abs() < 1e-8);
+            let (a, b) = (free.uncertainties.as_ref().unwrap()[i], bounded.uncertainties.as_ref().unwrap()[i]);
+            assert!((a - b).abs() < 1e-6 * a);
+        }
+    }
+}
@@ -0,0 +1,295 @@
+use nalgebra::{DMatrix, DVector};
+use num_traits::Float;
+
+use super::{values, FitReport, FitStopReason};
+use crate::{Differential, JacobianEvaluation};
+
+/// A robust loss `ρ(s)` of a squared residual `s = r²`
+///
+/// Every loss takes a scale `c`: residuals much smaller than `c` are treated like in least squares
+/// (`ρ(s) ≈ s`), larger ones are down-weighted. The losses are scaled as `c² ρ(s / c²)`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Loss {
+    /// Plain least squares, `ρ(s) = s`
+    Squared,
+
+    /// `ρ(s) = s` for `s ≤ 1`, `2√s - 1` otherwise
+    Huber(f64),
+
+    /// `ρ(s) = ln(1 + s)`
+    Cauchy(f64),
+
+    /// `ρ(s) = 2(√(1 + s) - 1)`
+    SoftL1(f64),
+
+    /// Tukey's biweight, `ρ(s) = (1 - (1 - s)³) / 3` for `s ≤ 1`, `1/3` otherwise
+    ///
+    /// Residuals beyond the scale are ignored entirely.
+    Tukey(f64),
+
+    /// `ρ(s) = atan(s)`
+    Arctan(f64),
+}
+
+impl Loss {
+    /// Evaluates the loss of a squared residual
+    ///
+    /// With `s` seeded as `Differential::new(r², 1.0)`, the derivative is the IRLS weight `ρ'(r²)`.
+    pub fn evaluate(&self, s: Differential) -> Differential {
+        let one = Differential::from(1.0);
+        let scaled = |c: f64, rho: &dyn Fn(Differential) -> Differential| rho(s * (c * c).recip()) * (c * c);
+        match *self {
+            Self::Squared => s,
+            Self::Huber(c) => scaled(c, &|s| if s.value <= 1.0 { s } else { s.sqrt() * 2.0 - one }),
+            Self::Cauchy(c) => scaled(c, &|s| s.ln_1p()),
+            Self::SoftL1(c) => scaled(c, &|s| ((one + s).sqrt() - one) * 2.0),
+            Self::Tukey(c) => scaled(c, &|s| {
+                if s.value <= 1.0 {
+                    (one - (one - s).powi(3)) * (1.0 / 3.0)
+                } else {
+                    Differential::new(1.0 / 3.0, 0.0)
+                }
+            }),
+            Self::Arctan(c) => scaled(c, &|s| s.atan()),
+        }
+    }
+
+    /// Wraps residuals so that their plain sum of squares is `Σ ρ(rᵢ²)`
+    ///
+    /// The wrapped residual is `sign(r) √ρ(r²)`; its derivative follows from the chain rule, so the
+    /// result can be passed to any least-squares solver, e.g. [`levenberg_marquardt`](super::levenberg_marquardt).
+    pub fn wrap(&self, residuals: Vec<Differential>) -> Vec<Differential> {
+        residuals
+            .into_iter()
+            .map(|r| {
+                let rho = self.evaluate(r * r);
+                if rho.value > 0.0 {
+                    rho.sqrt() * r.value.signum()
+                } else {
+                    // √ρ(r²) ≈ |r| √ρ'(0) for small residuals
+                    r * self.evaluate(Differential::new(0.0, 1.0)).derivative.sqrt()
+                }
+            })
+            .collect()
+    }
+}
+
+/// Options of [`irls`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct IrlsOptions {
+    /// Stop when an iteration reduces the cost `Σ ρ(rᵢ²)` by at most this fraction
+    pub cost_tolerance: f64,
+
+    /// Stop when a step is at most `parameter_tolerance * (‖p‖ + parameter_tolerance)`
+    pub parameter_tolerance: f64,
+
+    /// Stop when the largest component of the gradient `JᵀWr` is at most this
+    pub gradient_tolerance: f64,
+
+    /// Maximum number of iterations
+    pub max_iterations: usize,
+
+    /// Maximum number of step halvings per iteration
+    pub max_backtracks: usize,
+}
+
+impl Default for IrlsOptions {
+    fn default() -> Self {
+        Self {
+            cost_tolerance: 1e-12,
+            parameter_tolerance: 1e-12,
+            gradient_tolerance: 1e-12,
+            max_iterations: 200,
+            max_backtracks: 30,
+        }
+    }
+}
+
+/// The result of [`irls`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct RobustFitReport {
+    /// The fit statistics of the final weighted problem
+    ///
+    /// `residuals` are the unweighted residuals, while `chi_squared` and `covariance` use the
+    /// weighted residuals `√wᵢ rᵢ`.
+    pub fit: FitReport,
+
+    /// The robust cost `Σ ρ(rᵢ²)`
+    pub cost: f64,
+
+    /// The final weights `wᵢ = ρ'(rᵢ²)`; outliers have small weights
+    pub weights: Vec<f64>,
+}
+
+/// The loss values and weights of residuals
+fn weigh(loss: &Loss, residuals: &[f64]) -> (f64, DVector<f64>) {
+    let mut cost = 0.0;
+    let weights = residuals.iter().map(|r| {
+        let rho = loss.evaluate(Differential::new(r * r, 1.0));
+        cost += rho.value;
+        rho.derivative.max(0.0)
+    });
+    let weights = DVector::from_iterator(residuals.len(), weights);
+    (cost, weights)
+}
+
+/// Fits the parameters minimizing the robust cost `Σ ρ(rᵢ(p)²)` with iteratively reweighted
+/// Gauss–Newton
+///
+/// Each iteration weighs the residuals with `wᵢ = ρ'(rᵢ²)`, obtained by differentiating the loss,
+/// and solves `JᵀWJ δ = -JᵀWr`. The steps are halved until the robust cost decreases.
+pub fn irls(
+    residuals: impl Fn(&[Differential]) -> Vec<Differential>,
+    params: &[f64],
+    loss: Loss,
+    options: &IrlsOptions,
+) -> RobustFitReport {
+    let mut evaluation = JacobianEvaluation::new(&residuals, params);
+    let (mut cost, mut weights) = weigh(&loss, &evaluation.outputs);
+    let mut iterations = 0;
+
+    let reason = loop {
+        if !cost.is_finite() {
+            break FitStopReason::NonFinite;
+        }
+        let j = &evaluation.jacobian;
+        let r = DVector::from_column_slice(&evaluation.outputs);
+        let gradient = j.tr_mul(&r.component_mul(&weights));
+        if gradient.amax() <= options.gradient_tolerance {
+            break FitStopReason::GradientTolerance;
+        }
+        if iterations >= options.max_iterations {
+            break FitStopReason::MaxIterations;
+        }
+        iterations += 1;
+
+        let normal = weighted_normal(j, &weights);
+        let Some(step) = regularized_solve(normal, &gradient) else {
+            break FitStopReason::Stagnation;
+        };
+        let p = DVector::from_column_slice(&evaluation.params);
+        let mut alpha = 1.0;
+        let mut accepted = None;
+        for _ in 0..=options.max_backtracks {
+            let candidate = &p + &step * alpha;
+            let (candidate_cost, candidate_weights) = weigh(&loss, &values(&residuals, candidate.as_slice()));
+            if candidate_cost.is_finite() && candidate_cost < cost {
+                accepted = Some((candidate, candidate_cost, candidate_weights));
+                break;
+            }
+            alpha *= 0.5;
+        }
+        let Some((next, next_cost, next_weights)) = accepted else {
+            break FitStopReason::Stagnation;
+        };
+
+        let reduction = (cost - next_cost) / cost;
+        let step_norm = alpha * step.norm();
+        // the Jacobian is only needed at the accepted point
+        evaluation = JacobianEvaluation::new(&residuals, next.as_slice());
+        cost = next_cost;
+        weights = next_weights;
+        if step_norm <= options.parameter_tolerance * (DVector::from_column_slice(&evaluation.params).norm() + options.parameter_tolerance) {
+            break FitStopReason::ParameterTolerance;
+        }
+        if reduction <= options.cost_tolerance {
+            break FitStopReason::ChiSquaredTolerance;
+        }
+    };
+
+    // the statistics of the weighted problem with residuals √wᵢ rᵢ
+    let roots = weights.map(f64::sqrt);
+    let weighted = JacobianEvaluation {
+        outputs: evaluation.outputs.iter().zip(roots.iter()).map(|(r, w)| r * w).collect(),
+        jacobian: DMatrix::from_diagonal(&roots) * &evaluation.jacobian,
+        ..evaluation.clone()
+    };
+    let chi_squared = weighted.outputs.iter().map(|r| r * r).sum();
+    let fit = FitReport {
+        residuals: evaluation.outputs,
+        ..FitReport::new(weighted, chi_squared, iterations, reason)
+    };
+    RobustFitReport { fit, cost, weights: weights.iter().copied().collect() }
+}
+
+/// `JᵀWJ` for diagonal weights `W`
+fn weighted_normal(j: &DMatrix<f64>, weights: &DVector<f64>) -> DMatrix<f64> {
+    let mut weighted = j.clone();
+    for (mut row, w) in weighted.row_iter_mut().zip(weights.iter()) {
+        row *= *w;
+    }
+    j.tr_mul(&weighted)
+}
+
+/// Solves `A δ = -g`, adding Levenberg–Marquardt damping while `A` is not positive definite
+fn regularized_solve(normal: DMatrix<f64>, gradient: &DVector<f64>) -> Option<DVector<f64>> {
+    let mut damping = 0.0;
+    while damping <= 1e16 {
+        let mut damped = normal.clone();
+        for i in 0..damped.nrows() {
+            damped[(i, i)] += damping * normal[(i, i)].max(f64::EPSILON) + f64::EPSILON;
+        }
+        if let Some(cholesky) = damped.cholesky() {
+            return Some(-cholesky.solve(gradient));
+        }
+        damping = if damping == 0.0 { 1e-8 } else { damping * 10.0 };
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fit::levenberg_marquardt;
+
+    /// A line `y = 1 + 2x` with small noise and two gross outliers
+    fn data() -> Vec<(f64, f64)> {
+        let noise = [0.01, -0.02, 0.0, 0.015, -0.01, 5.0, 0.02, -0.015, -4.0, 0.005];
+        noise.iter().enumerate().map(|(i, e)| (i as f64, 1.0 + 2.0 * i as f64 + e)).collect()
+    }
+
+    fn line(points: &[(f64, f64)]) -> impl Fn(&[Differential]) -> Vec<Differential> + '_ {
+        move |p| points.iter().map(|&(x, y)| p[0] + p[1] * x - Differential::from(y)).collect()
+    }
+
+    #[test]
+    fn loss_weights() {
+        let weight = |loss: Loss, r: f64| loss.evaluate(Differential::new(r * r, 1.0)).derivative;
+        for loss in [Loss::Huber(1.0), Loss::Cauchy(1.0), Loss::SoftL1(1.0), Loss::Tukey(1.0), Loss::Arctan(1.0)] {
+            assert!((weight(loss, 1e-4) - 1.0).abs() < 1e-6, "{:?}", loss);
+            assert!(weight(loss, 3.0) < 0.5, "{:?}", loss);
+        }
+        assert_eq!(weight(Loss::Huber(1.0), 4.0), 0.25);
+        assert_eq!(weight(Loss::Tukey(1.0), 1.5), 0.0);
+        assert_eq!(weight(Loss::Squared, 100.0), 1.0);
+        // scaling: c² ρ(s / c²) has the weight ρ'(s / c²)
+        assert_eq!(weight(Loss::Huber(2.0), 4.0), weight(Loss::Huber(1.0), 2.0));
+    }
+
+    #[test]
+    fn robust_fit_ignores_outliers() {
+        let points = data();
+        let plain = levenberg_marquardt(line(&points), &[0.0, 0.0], &Default::default());
+        assert!((plain.params[1] - 2.0).abs() > 0.05);
+
+        for loss in [Loss::Huber(0.1), Loss::Cauchy(0.1), Loss::SoftL1(0.1), Loss::Tukey(0.5), Loss::Arctan(0.1)] {
+            // Tukey is not convex, so start from the least-squares fit
+            let report = irls(line(&points), &plain.params, loss, &Default::default());
+            assert!(report.fit.converged(), "{:?}", loss);
+            assert!((report.fit.params[0] - 1.0).abs() < 0.05, "{:?} {:?}", loss, report.fit.params);
+            assert!((report.fit.params[1] - 2.0).abs() < 0.01, "{:?} {:?}", loss, report.fit.params);
+            assert!(report.weights[5] < 0.1 && report.weights[8] < 0.1, "{:?}", loss);
+            assert!((report.fit.residuals[5] + 5.0).abs() < 0.1);
+        }
+    }
+
+    #[test]
+    fn wrapped_residuals_match_irls() {
+        let points = data();
+        let loss = Loss::Cauchy(0.1);
+        let wrapped = levenberg_marquardt(|p| loss.wrap(line(&points)(p)), &[0.0, 0.0], &Default::default());
+        let report = irls(line(&points), &[0.0, 0.0], loss, &Default::default());
+        assert!((wrapped.chi_squared - report.cost).abs() < 1e-9);
+        assert!((wrapped.params[1] - report.fit.params[1]).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,233 @@
+/*!
+Differentiation through the solutions of nonlinear systems with the implicit function theorem.
+
+When `x*(p)` solves `F(x, p) = 0`, its derivatives are `dx/dp = -(∂F/∂x)⁻¹ ∂F/∂p`, regardless of
+how `x*` was found. Differentiating the solver iterations instead is slow and inaccurate.
+
+The minimizers `x*(p) = argmin_x g(x, p)` of smooth problems solve `∇ₓg(x, p) = 0`, so the same
+applies to them with `dx/dp = -(∂²g/∂x²)⁻¹ ∂²g/∂x∂p`, as in bilevel optimization.
+*/
+
+use core::fmt;
+use core::ops::{Add, Mul};
+
+use nalgebra::DMatrix;
+use num_traits::Zero;
+
+use crate::optimize::hessian_vector_product;
+use crate::{jacobian, Differential, Differential2};
+
+/// An error of the implicit differentiation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImplicitError {
+    /// `F` does not have one output per unknown
+    NotSquare,
+
+    /// `∂F/∂x`, or the Hessian `∂²g/∂x²` of a minimization, is singular at the solution, so `x*(p)` is
+    /// not locally unique
+    SingularJacobian,
+}
+
+impl fmt::Display for ImplicitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ImplicitError::NotSquare => write!(f, "the system must have one equation per unknown"),
+            ImplicitError::SingularJacobian => write!(f, "the Jacobian with respect to the unknowns is singular"),
+        }
+    }
+}
+
+impl core::error::Error for ImplicitError {}
+
+/// Computes the sensitivities `dx/dp = -(∂F/∂x)⁻¹ ∂F/∂p` of a solution `x` of `F(x, p) = 0`
+///
+/// `f` takes the unknowns and the parameters. The result has one row per unknown and one column
+/// per parameter.
+pub fn implicit_jacobian(
+    f: impl Fn(&[Differential], &[Differential]) -> Vec<Differential>,
+    x: &[f64],
+    p: &[f64],
+) -> Result<DMatrix<f64>, ImplicitError> {
+    let n = x.len();
+    let z: Vec<f64> = x.iter().chain(p).copied().collect();
+    let jacobian = jacobian(|z| f(&z[..n], &z[n..]), &z);
+    if jacobian.nrows() != n {
+        return Err(ImplicitError::NotSquare);
+    }
+    let dx = jacobian.columns(0, n).into_owned();
+    let dp = jacobian.columns(n, p.len()).into_owned();
+    let sensitivities = dx.lu().solve(&-dp).ok_or(ImplicitError::SingularJacobian)?;
+    if sensitivities.iter().all(|s| s.is_finite()) {
+        Ok(sensitivities)
+    } else {
+        Err(ImplicitError::SingularJacobian)
+    }
+}
+
+/// Attaches derivatives to a converged solution `x` of `F(x, p) = 0`
+///
+/// The parameters `p` carry arbitrary tangents, e.g. a scalar derivative or a vector of partial
+/// derivatives. The returned differentials have the values `x` and the tangents propagated through
+/// the implicit function theorem, so that downstream code can keep differentiating through the
+/// solution.
+pub fn implicit_solution<D>(
+    f: impl Fn(&[Differential], &[Differential]) -> Vec<Differential>,
+    x: &[f64],
+    p: &[Differential<f64, D>],
+) -> Result<Vec<Differential<f64, D>>, ImplicitError>
+where
+    D: Clone + Zero + Add<Output = D> + Mul<f64, Output = D>,
+{
+    let values: Vec<f64> = p.iter().map(|p| p.value).collect();
+    Ok(propagate(x, p, &implicit_jacobian(f, x, &values)?))
+}
+
+/// Computes the sensitivities `dx/dp = -(∂²g/∂x²)⁻¹ ∂²g/∂x∂p` of a minimizer `x` of `g(x, p)`
+///
+/// `g` takes the variables and the parameters. The second derivatives come from nested
+/// differentials and take `n (n + m)` evaluations of `g` for `n` variables and `m` parameters.
+pub fn argmin_jacobian(
+    g: impl Fn(&[Differential2], &[Differential2]) -> Differential2,
+    x: &[f64],
+    p: &[f64],
+) -> Result<DMatrix<f64>, ImplicitError> {
+    let n = x.len();
+    let z: Vec<f64> = x.iter().chain(p).copied().collect();
+    // the columns of the Hessian for the variables hold both ∂²g/∂x² and (∂²g/∂x∂p)ᵀ
+    let mut columns = DMatrix::zeros(z.len(), n);
+    for j in 0..n {
+        let mut direction = vec![0.0; z.len()];
+        direction[j] = 1.0;
+        columns.set_column(j, &hessian_vector_product(|z| g(&z[..n], &z[n..]), &z, &direction));
+    }
+    let hessian = columns.rows(0, n).into_owned();
+    let mixed = columns.rows(n, p.len()).transpose();
+    let sensitivities = hessian.lu().solve(&-mixed).ok_or(ImplicitError::SingularJacobian)?;
+    if sensitivities.iter().all(|s| s.is_finite()) {
+        Ok(sensitivities)
+    } else {
+        Err(ImplicitError::SingularJacobian)
+    }
+}
+
+/// Attaches derivatives to a minimizer `x` of `g(x, p)`
+///
+/// This is [`implicit_solution`] for the optimality condition `∇ₓg(x, p) = 0`: the tangents of `p`
+/// are propagated to `x`, so the gradient of an outer objective can be taken through the inner
+/// minimization.
+pub fn argmin_solution<D>(
+    g: impl Fn(&[Differential2], &[Differential2]) -> Differential2,
+    x: &[f64],
+    p: &[Differential<f64, D>],
+) -> Result<Vec<Differential<f64, D>>, ImplicitError>
+where
+    D: Clone + Zero + Add<Output = D> + Mul<f64, Output = D>,
+{
+    let values: Vec<f64> = p.iter().map(|p| p.value).collect();
+    Ok(propagate(x, p, &argmin_jacobian(g, x, &values)?))
+}
+
+/// Combines the tangents of `p` with the sensitivities into the tangents of `x`
+fn propagate<D>(x: &[f64], p: &[Differential<f64, D>], sensitivities: &DMatrix<f64>) -> Vec<Differential<f64, D>>
+where
+    D: Clone + Zero + Add<Output = D> + Mul<f64, Output = D>,
+{
+    x.iter()
+        .enumerate()
+        .map(|(i, &x)| {
+            let derivative = p
+                .iter()
+                .enumerate()
+                .fold(D::zero(), |acc, (j, p)| acc + p.derivative.clone() * sensitivities[(i, j)]);
+            Differential::new(x, derivative)
+        })
+        .collect()
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use nalgebra::Vector2;
+    use num_traits::Float;
+
+    use super::*;
+    use crate::optimize::{trust_region, TrustRegionOptions};
+    use crate::solve::{newton_1d, Newton1dOptions};
+
+    #[test]
+    fn scalar_equation() {
+        // x³ + p x - 1 = 0, so dx/dp = -x / (3x² + p)
+        let f = |x: &[Differential], p: &[Differential]| vec![x[0] * x[0] * x[0] + p[0] * x[0] - Differential::from(1.0)];
+        let p = 2.0;
+        let root = newton_1d(|x| x * x * x + x * p - Differential::from(1.0), 0.5, &Newton1dOptions::default()).root;
+        let x = implicit_solution(f, &[root], &[Differential::new(p, 1.0)]).unwrap();
+        assert_eq!(x[0].value, root);
+        assert!((x[0].derivative + root / (3.0 * root * root + p)).abs() < 1e-12);
+    }
+
+    #[test]
+    fn vector_tangents_and_downstream_propagation() {
+        // the intersection of x + y = a and x - y = b
+        let f = |x: &[Differential], p: &[Differential]| vec![x[0] + x[1] - p[0], x[0] - x[1] - p[1]];
+        let p = [Differential::new(3.0, Vector2::new(1.0, 0.0)), Differential::new(1.0, Vector2::new(0.0, 1.0))];
+        let x = implicit_solution(f, &[2.0, 1.0], &p).unwrap();
+        assert_eq!(x[0].derivative, Vector2::new(0.5, 0.5));
+        assert_eq!(x[1].derivative, Vector2::new(0.5, -0.5));
+
+        // d(x y)/da = y dx/da + x dy/da
+        let product = x[0] * x[1];
+        assert_eq!(product.derivative, Vector2::new(1.5, -0.5));
+        assert!((x[0].exp().derivative[0] - 0.5 * 2f64.exp()).abs() < 1e-12);
+    }
+
+    #[test]
+    fn errors() {
+        let singular = |x: &[Differential], p: &[Differential]| vec![x[0] * x[0] - p[0]];
+        assert_eq!(implicit_jacobian(singular, &[0.0], &[0.0]), Err(ImplicitError::SingularJacobian));
+        let not_square = |x: &[Differential], p: &[Differential]| vec![x[0] - p[0], x[0] + p[0]];
+        assert_eq!(implicit_jacobian(not_square, &[0.0], &[0.0]), Err(ImplicitError::NotSquare));
+        assert_eq!(ImplicitError::NotSquare.to_string(), "the system must have one equation per unknown");
+    }
+
+    #[test]
+    fn ridge_minimizer() {
+        // g = (x - 3)² + λ x² has x* = 3 / (1 + λ) and dx*/dλ = -3 / (1 + λ)²
+        fn ridge<T: Float>(x: &[T], p: &[T]) -> T {
+            (x[0] - T::from(3.0).unwrap()).powi(2) + p[0] * x[0] * x[0]
+        }
+        let lambda = 0.5;
+        let x = argmin_solution(ridge, &[3.0 / (1.0 + lambda)], &[Differential::new(lambda, 1.0)]).unwrap();
+        assert!((x[0].derivative + 3.0 / (1.0 + lambda).powi(2)).abs() < 1e-12);
+    }
+
+    #[test]
+    fn outer_gradient_through_inner_fit() {
+        // the inner problem fits a line by least squares with two regularization terms
+        fn inner<T: Float>(x: &[T], p: &[T]) -> T {
+            let data = [(0.0, 1.0), (1.0, 2.5), (2.0, 2.9), (3.0, 4.2)];
+            let fit = data.iter().fold(T::zero(), |acc, &(t, y)| {
+                let r = x[0] + x[1] * T::from(t).unwrap() - T::from(y).unwrap();
+                acc + r * r
+            });
+            fit + p[0] * x[1] * x[1] + p[1] * (x[0] * x[1] - T::one()).powi(2)
+        }
+        let solve = |p: &[f64]| {
+            let options = TrustRegionOptions { gradient_tolerance: 1e-10, ..Default::default() };
+            let p: Vec<Differential2> = p.iter().map(|&p| Differential::from(Differential::from(p))).collect();
+            let report = trust_region(|x| inner(x, &p), &[1.0, 1.0], &options);
+            assert!(report.converged());
+            report.x
+        };
+        // the outer objective is the slope of the fit
+        let p = [0.3, 0.2];
+        let x = solve(&p);
+        let dx = argmin_jacobian(inner, &x, &p).unwrap();
+        let h = 1e-6;
+        for j in 0..2 {
+            let (mut plus, mut minus) = (p, p);
+            plus[j] += h;
+            minus[j] -= h;
+            let central = (solve(&plus)[1] - solve(&minus)[1]) / (2.0 * h);
+            assert!((dx[(1, j)] - central).abs() < 1e-6, "{} {}", dx[(1, j)], central);
+        }
+    }
+}
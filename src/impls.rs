@@ -3,5 +3,8 @@ use super::*;
 
 mod std_ops;
 mod num_traits_impl;
+mod conversions;
+#[cfg(feature = "approx")]
 mod approx_impl;
-mod nalgebra;
\ No newline at end of file
+#[cfg(feature = "nalgebra")]
+mod nalgebra;
@@ -0,0 +1,170 @@
+use super::*;
+
+macro_rules! scalar_forms {
+    ($($t:ty),*) => {
+        $(
+            impl IntoDifferentialForm for $t {
+                type DifferentialForm = Differential<$t>;
+
+                fn into_differential_form(self) -> Self::DifferentialForm {
+                    self.into()
+                }
+            }
+        )*
+    };
+}
+
+scalar_forms!(f32, f64);
+
+#[cfg(any(feature = "std", feature = "libm"))]
+scalar_forms!(crate::interval::Interval);
+
+/// Nests the differential, as for the second derivatives of [`Differential2`]
+impl<T, D> IntoDifferentialForm for Differential<T, D>
+where
+    Self: Zero,
+{
+    type DifferentialForm = Differential<Self>;
+
+    fn into_differential_form(self) -> Self::DifferentialForm {
+        self.into()
+    }
+}
+
+impl<T, D> IntoRealForm for Differential<T, D> {
+    type RealForm = T;
+
+    fn into_real_form(self) -> Self::RealForm {
+        self.value
+    }
+}
+
+impl<T, D> IntoJacobianForm for Differential<T, D> {
+    type RealForm = T;
+    type JacobianForm = D;
+
+    fn into_jacobian_form(self) -> (Self::RealForm, Self::JacobianForm) {
+        (self.value, self.derivative)
+    }
+}
+
+impl<T, const N: usize> IntoDifferentialForm for [T; N]
+where
+    T: IntoDifferentialForm,
+{
+    type DifferentialForm = [T::DifferentialForm; N];
+
+    fn into_differential_form(self) -> Self::DifferentialForm {
+        self.map(T::into_differential_form)
+    }
+}
+
+impl<T, const N: usize> IntoRealForm for [T; N]
+where
+    T: IntoRealForm,
+{
+    type RealForm = [T::RealForm; N];
+
+    fn into_real_form(self) -> Self::RealForm {
+        self.map(T::into_real_form)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T> IntoDifferentialForm for Vec<T>
+where
+    T: IntoDifferentialForm,
+{
+    type DifferentialForm = Vec<T::DifferentialForm>;
+
+    fn into_differential_form(self) -> Self::DifferentialForm {
+        self.into_iter().map(T::into_differential_form).collect()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T> IntoRealForm for Vec<T>
+where
+    T: IntoRealForm,
+{
+    type RealForm = Vec<T::RealForm>;
+
+    fn into_real_form(self) -> Self::RealForm {
+        self.into_iter().map(T::into_real_form).collect()
+    }
+}
+
+impl<T> IntoDifferentialForm for Option<T>
+where
+    T: IntoDifferentialForm,
+{
+    type DifferentialForm = Option<T::DifferentialForm>;
+
+    fn into_differential_form(self) -> Self::DifferentialForm {
+        self.map(T::into_differential_form)
+    }
+}
+
+impl<T> IntoRealForm for Option<T>
+where
+    T: IntoRealForm,
+{
+    type RealForm = Option<T::RealForm>;
+
+    fn into_real_form(self) -> Self::RealForm {
+        self.map(T::into_real_form)
+    }
+}
+
+macro_rules! tuple_forms {
+    ($(($($t:ident $i:tt),+)),*) => {
+        $(
+            impl<$($t),+> IntoDifferentialForm for ($($t,)+)
+            where
+                $($t: IntoDifferentialForm),+
+            {
+                type DifferentialForm = ($($t::DifferentialForm,)+);
+
+                fn into_differential_form(self) -> Self::DifferentialForm {
+                    ($(self.$i.into_differential_form(),)+)
+                }
+            }
+
+            impl<$($t),+> IntoRealForm for ($($t,)+)
+            where
+                $($t: IntoRealForm),+
+            {
+                type RealForm = ($($t::RealForm,)+);
+
+                fn into_real_form(self) -> Self::RealForm {
+                    ($(self.$i.into_real_form(),)+)
+                }
+            }
+
+            impl<$($t),+> IntoJacobianForm for ($($t,)+)
+            where
+                $($t: IntoJacobianForm),+
+            {
+                type RealForm = ($($t::RealForm,)+);
+                type JacobianForm = ($($t::JacobianForm,)+);
+
+                #[allow(non_snake_case)]
+                fn into_jacobian_form(self) -> (Self::RealForm, Self::JacobianForm) {
+                    let ($($t,)+) = ($(self.$i.into_jacobian_form(),)+);
+                    (($($t.0,)+), ($($t.1,)+))
+                }
+            }
+        )*
+    };
+}
+
+tuple_forms!(
+    (A 0),
+    (A 0, B 1),
+    (A 0, B 1, C 2),
+    (A 0, B 1, C 2, D 3),
+    (A 0, B 1, C 2, D 3, E 4),
+    (A 0, B 1, C 2, D 3, E 4, F 5),
+    (A 0, B 1, C 2, D 3, E 4, F 5, G 6),
+    (A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7)
+);
@@ -1,49 +1,326 @@
+use ::nalgebra::{
+    allocator::Allocator, Complex, DefaultAllocator, Dim, DimName, Isometry, Matrix, OMatrix, OPoint, OVector,
+    Quaternion, RawStorage, Rotation, Scalar, Similarity, Translation, Unit, U1,
+};
+use num_traits::{Num, NumCast, ToPrimitive};
 
-use ::nalgebra::{Vector2, Vector3};
-use num_traits::real::Real;
+use crate::text::{NumberStyle, ParseDifferentialError, TangentText, EPSILON_SYMBOL};
 
 use super::*;
 
-impl<T> IntoDifferentialForm for Vector2<T>
+impl<T, R, C, S> IntoDifferentialForm for Matrix<T, R, C, S>
 where
-    T: Debug + Real + 'static,
+    T: Scalar + IntoDifferentialForm,
+    T::DifferentialForm: Scalar,
+    R: Dim,
+    C: Dim,
+    S: RawStorage<T, R, C>,
+    DefaultAllocator: Allocator<T::DifferentialForm, R, C>,
 {
-    type DifferentialForm = Vector2<Differential<T>>;
+    type DifferentialForm = OMatrix<T::DifferentialForm, R, C>;
 
     fn into_differential_form(self) -> Self::DifferentialForm {
-        Vector2::new(self.x.into(), self.y.into())
+        self.map(T::into_differential_form)
     }
 }
 
-impl<T> IntoRealForm for Vector2<Differential<T>>
+impl<T, R, C, S> IntoRealForm for Matrix<T, R, C, S>
 where
-    T: Debug + Real + 'static,
+    T: Scalar + IntoRealForm,
+    T::RealForm: Scalar,
+    R: Dim,
+    C: Dim,
+    S: RawStorage<T, R, C>,
+    DefaultAllocator: Allocator<T::RealForm, R, C>,
 {
-    type RealForm = Vector2<T>;
+    type RealForm = OMatrix<T::RealForm, R, C>;
 
     fn into_real_form(self) -> Self::RealForm {
-        Vector2::new(self.x.value, self.y.value)
+        self.map(T::into_real_form)
     }
 }
 
-impl<T> IntoDifferentialForm for Vector3<T>
+/// The derivatives of the components become the rows of the Jacobian block
+///
+/// All the derivatives must have the same length, which only matters for dynamically sized ones.
+///
+/// # Panics
+///
+/// Panics if the lengths of the derivatives differ.
+impl<T, R, N, S> IntoJacobianForm for Matrix<Differential<T, OVector<T, N>>, R, U1, S>
 where
-    T: Debug + Real + 'static,
+    T: Scalar,
+    R: Dim,
+    N: Dim,
+    S: RawStorage<Differential<T, OVector<T, N>>, R, U1>,
+    DefaultAllocator: Allocator<T, N> + Allocator<T, R> + Allocator<T, R, N>,
 {
-    type DifferentialForm = Vector3<Differential<T>>;
+    type RealForm = OVector<T, R>;
+    type JacobianForm = OMatrix<T, R, N>;
+
+    fn into_jacobian_form(self) -> (Self::RealForm, Self::JacobianForm) {
+        let (rows, _) = self.shape_generic();
+        let tangents = match self.get(0) {
+            Some(x) => x.derivative.shape_generic().0,
+            None => N::from_usize(N::try_to_usize().unwrap_or(0)),
+        };
+        for (i, x) in self.iter().enumerate() {
+            assert_eq!(
+                x.derivative.len(),
+                tangents.value(),
+                "the derivative of component {} has a different length than the first one",
+                i
+            );
+        }
+        let values = self.map(|x| x.value);
+        let jacobian = OMatrix::from_fn_generic(rows, tangents, |i, j| self[i].derivative[j].clone());
+        (values, jacobian)
+    }
+}
+
+impl<T, D> IntoDifferentialForm for OPoint<T, D>
+where
+    T: Scalar + IntoDifferentialForm,
+    T::DifferentialForm: Scalar,
+    D: DimName,
+    DefaultAllocator: Allocator<T, D> + Allocator<T::DifferentialForm, D>,
+{
+    type DifferentialForm = OPoint<T::DifferentialForm, D>;
+
+    fn into_differential_form(self) -> Self::DifferentialForm {
+        OPoint::from(self.coords.into_differential_form())
+    }
+}
+
+impl<T, D> IntoRealForm for OPoint<T, D>
+where
+    T: Scalar + IntoRealForm,
+    T::RealForm: Scalar,
+    D: DimName,
+    DefaultAllocator: Allocator<T, D> + Allocator<T::RealForm, D>,
+{
+    type RealForm = OPoint<T::RealForm, D>;
+
+    fn into_real_form(self) -> Self::RealForm {
+        OPoint::from(self.coords.into_real_form())
+    }
+}
+
+impl<T, const D: usize> IntoDifferentialForm for Translation<T, D>
+where
+    T: Scalar + IntoDifferentialForm,
+    T::DifferentialForm: Scalar,
+{
+    type DifferentialForm = Translation<T::DifferentialForm, D>;
+
+    fn into_differential_form(self) -> Self::DifferentialForm {
+        Translation::from(self.vector.into_differential_form())
+    }
+}
+
+impl<T, const D: usize> IntoRealForm for Translation<T, D>
+where
+    T: Scalar + IntoRealForm,
+    T::RealForm: Scalar,
+{
+    type RealForm = Translation<T::RealForm, D>;
+
+    fn into_real_form(self) -> Self::RealForm {
+        Translation::from(self.vector.into_real_form())
+    }
+}
+
+impl<T, const D: usize> IntoDifferentialForm for Rotation<T, D>
+where
+    T: Scalar + IntoDifferentialForm,
+    T::DifferentialForm: Scalar,
+{
+    type DifferentialForm = Rotation<T::DifferentialForm, D>;
+
+    fn into_differential_form(self) -> Self::DifferentialForm {
+        Rotation::from_matrix_unchecked(self.into_inner().into_differential_form())
+    }
+}
+
+impl<T, const D: usize> IntoRealForm for Rotation<T, D>
+where
+    T: Scalar + IntoRealForm,
+    T::RealForm: Scalar,
+{
+    type RealForm = Rotation<T::RealForm, D>;
+
+    fn into_real_form(self) -> Self::RealForm {
+        Rotation::from_matrix_unchecked(self.into_inner().into_real_form())
+    }
+}
+
+impl<T> IntoDifferentialForm for Quaternion<T>
+where
+    T: Scalar + IntoDifferentialForm,
+    T::DifferentialForm: Scalar,
+{
+    type DifferentialForm = Quaternion<T::DifferentialForm>;
+
+    fn into_differential_form(self) -> Self::DifferentialForm {
+        Quaternion::from(self.coords.into_differential_form())
+    }
+}
+
+impl<T> IntoRealForm for Quaternion<T>
+where
+    T: Scalar + IntoRealForm,
+    T::RealForm: Scalar,
+{
+    type RealForm = Quaternion<T::RealForm>;
+
+    fn into_real_form(self) -> Self::RealForm {
+        Quaternion::from(self.coords.into_real_form())
+    }
+}
+
+impl<T> IntoDifferentialForm for Complex<T>
+where
+    T: IntoDifferentialForm,
+{
+    type DifferentialForm = Complex<T::DifferentialForm>;
+
+    fn into_differential_form(self) -> Self::DifferentialForm {
+        Complex::new(self.re.into_differential_form(), self.im.into_differential_form())
+    }
+}
+
+impl<T> IntoRealForm for Complex<T>
+where
+    T: IntoRealForm,
+{
+    type RealForm = Complex<T::RealForm>;
+
+    fn into_real_form(self) -> Self::RealForm {
+        Complex::new(self.re.into_real_form(), self.im.into_real_form())
+    }
+}
+
+/// Unit quaternions and unit complex numbers keep their normalization, which the conversions preserve
+impl<T> IntoDifferentialForm for Unit<T>
+where
+    T: IntoDifferentialForm,
+{
+    type DifferentialForm = Unit<T::DifferentialForm>;
 
     fn into_differential_form(self) -> Self::DifferentialForm {
-        Vector3::new(self.x.into(), self.y.into(), self.z.into())
+        Unit::new_unchecked(self.into_inner().into_differential_form())
     }
 }
 
-impl<T> IntoRealForm for Vector3<Differential<T>>
+impl<T> IntoRealForm for Unit<T>
 where
-    T: Debug + Real + 'static,
+    T: IntoRealForm,
 {
-    type RealForm = Vector3<T>;
+    type RealForm = Unit<T::RealForm>;
 
     fn into_real_form(self) -> Self::RealForm {
-        Vector3::new(self.x.value, self.y.value, self.z.value)
+        Unit::new_unchecked(self.into_inner().into_real_form())
+    }
+}
+
+impl<T, R, const D: usize> IntoDifferentialForm for Isometry<T, R, D>
+where
+    T: Scalar + IntoDifferentialForm,
+    T::DifferentialForm: Scalar,
+    R: IntoDifferentialForm,
+{
+    type DifferentialForm = Isometry<T::DifferentialForm, R::DifferentialForm, D>;
+
+    fn into_differential_form(self) -> Self::DifferentialForm {
+        Isometry {
+            rotation: self.rotation.into_differential_form(),
+            translation: self.translation.into_differential_form(),
+        }
     }
-}
\ No newline at end of file
+}
+
+impl<T, R, const D: usize> IntoRealForm for Isometry<T, R, D>
+where
+    T: Scalar + IntoRealForm,
+    T::RealForm: Scalar,
+    R: IntoRealForm,
+{
+    type RealForm = Isometry<T::RealForm, R::RealForm, D>;
+
+    fn into_real_form(self) -> Self::RealForm {
+        Isometry {
+            rotation: self.rotation.into_real_form(),
+            translation: self.translation.into_real_form(),
+        }
+    }
+}
+
+/// nalgebra can only build similarities over real fields, so the differential form of a similarity
+/// is its isometry together with its scaling factor
+///
+/// Its real form is the pair of the real isometry and scaling, from which
+/// [`Similarity::from_isometry`] rebuilds the similarity.
+impl<T, R, const D: usize> IntoDifferentialForm for Similarity<T, R, D>
+where
+    T: Scalar + IntoDifferentialForm,
+    T::DifferentialForm: Scalar,
+    R: IntoDifferentialForm,
+{
+    type DifferentialForm = (Isometry<T::DifferentialForm, R::DifferentialForm, D>, T::DifferentialForm);
+
+    fn into_differential_form(self) -> Self::DifferentialForm {
+        let scaling = self.scaling().into_differential_form();
+        (self.isometry.into_differential_form(), scaling)
+    }
+}
+
+impl<T, U, R, C, S> CastTangent<OMatrix<U, R, C>> for Matrix<T, R, C, S>
+where
+    T: Scalar + ToPrimitive,
+    U: Scalar + NumCast,
+    R: Dim,
+    C: Dim,
+    S: RawStorage<T, R, C>,
+    DefaultAllocator: Allocator<U, R, C>,
+{
+    fn cast_tangent(self) -> Option<OMatrix<U, R, C>> {
+        let components = self.iter().map(|x| U::from(x.clone())).collect::<Option<Vec<U>>>()?;
+        let (rows, columns) = self.shape_generic();
+        Some(OMatrix::from_iterator_generic(rows, columns, components))
+    }
+}
+
+impl<T, R> TangentText for OVector<T, R>
+where
+    T: Scalar + Num + std::fmt::Display + std::fmt::LowerExp + std::fmt::UpperExp,
+    R: Dim,
+    DefaultAllocator: Allocator<T, R>,
+{
+    fn fmt_tangent(&self, f: &mut std::fmt::Formatter<'_>, style: NumberStyle) -> std::fmt::Result {
+        write!(f, " + [")?;
+        for (i, x) in self.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            style.fmt(x, f)?;
+        }
+        write!(f, "]{}", EPSILON_SYMBOL)
+    }
+
+    fn parse_tangent(s: &str, negative: bool, radix: u32) -> Result<Self, ParseDifferentialError> {
+        let invalid = ParseDifferentialError::InvalidDerivative;
+        if negative {
+            return Err(invalid);
+        }
+        let components = s.strip_prefix('[').and_then(|s| s.strip_suffix(']')).ok_or(invalid)?;
+        let components = components
+            .split(',')
+            .map(|x| T::from_str_radix(x.trim(), radix).map_err(|_| invalid))
+            .collect::<Result<Vec<T>, _>>()?;
+        if R::try_to_usize().is_some_and(|n| n != components.len()) {
+            return Err(invalid);
+        }
+        let rows = R::from_usize(components.len());
+        Ok(OVector::from_iterator_generic(rows, U1, components))
+    }
+}
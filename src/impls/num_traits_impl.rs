@@ -1,7 +1,13 @@
 
-use std::ops::Neg;
+use core::ops::Neg;
+#[cfg(any(feature = "std", feature = "libm"))]
+use core::num::FpCategory;
 
-use num_traits::{real::Real, NumCast, ToPrimitive, Zero, One, Num, Signed, NumOps};
+#[cfg(any(feature = "std", feature = "libm"))]
+use num_traits::Float;
+use num_traits::{FloatConst, FromPrimitive, NumCast, ToPrimitive, Zero, One, Num, Signed, NumOps};
+
+use crate::text::ParseDifferentialError;
 
 use super::*;
 
@@ -11,7 +17,7 @@ impl<T, D> One for Differential<T, D>
 where
     T: One,
     D: Zero,
-    Self: std::ops::Mul<Output = Self>,
+    Self: core::ops::Mul<Output = Self>,
 {
     fn one() -> Differential<T, D> {
         Differential::new(T::one(), D::zero())
@@ -22,7 +28,7 @@ impl<T, D> Zero for Differential<T, D>
 where
     T: Zero,
     D: Zero,
-    Self: std::ops::Add<Output = Self>,
+    Self: core::ops::Add<Output = Self>,
 {
     fn zero() -> Differential<T, D> {
         Differential::new(T::zero(), D::zero())
@@ -39,15 +45,15 @@ where
     D: Zero,
     Self: NumOps,
 {
-    type FromStrRadixErr = FormStrRadixErr;
+    type FromStrRadixErr = ParseDifferentialError;
 
-    fn from_str_radix(_str: &str, _radix: u32) -> Result<Self, Self::FromStrRadixErr> {
-        todo!("from_str_radix is not implemented for Differential, yet.");
+    /// Parses a value without derivative, use [`Differential::from_str_radix_with_tangent`] to read a
+    /// derivative too
+    fn from_str_radix(str: &str, radix: u32) -> Result<Self, Self::FromStrRadixErr> {
+        Self::parse_value(str, radix)
     }
 }
 
-type FormStrRadixErr = ();
-
 impl<T, D> Signed for Differential<T, D>
 where
     T: Neg<Output = T> + Num + PartialOrd + Signed,
@@ -160,36 +166,161 @@ mod tests {
     }
 }
 
+macro_rules! forward_to_primitive {
+    ($($name:ident -> $ty:ty),* $(,)?) => {
+        $(
+            fn $name(&self) -> Option<$ty> {
+                self.value.$name()
+            }
+        )*
+    };
+}
+
 impl<T, D> ToPrimitive for Differential<T, D>
 where
     T: ToPrimitive,
 {
-    fn to_i64(&self) -> Option<i64> {
-        self.value.to_i64()
-    }
-
-    fn to_u64(&self) -> Option<u64> {
-        self.value.to_u64()
-    }
+    forward_to_primitive!(
+        to_isize -> isize, to_i8 -> i8, to_i16 -> i16, to_i32 -> i32, to_i64 -> i64, to_i128 -> i128,
+        to_usize -> usize, to_u8 -> u8, to_u16 -> u16, to_u32 -> u32, to_u64 -> u64, to_u128 -> u128,
+        to_f32 -> f32, to_f64 -> f64,
+    );
 }
 
 impl<T, D> NumCast for Differential<T, D>
 where
-    T: ToPrimitive + NumCast,
+    T: NumCast,
     D: Zero,
 {
     fn from<T2: ToPrimitive>(n: T2) -> Option<Self> {
-        let f = n.to_f64()?;
-        Some(Self::new(T::from(f).unwrap(), D::zero())) // TODO correct??? we are losing derivative and precision here!
+        T::from(n).map(|value| Self::new(value, D::zero()))
+    }
+}
+
+macro_rules! forward_from_primitive {
+    ($($name:ident($ty:ty)),* $(,)?) => {
+        $(
+            fn $name(n: $ty) -> Option<Self> {
+                T::$name(n).map(|value| Self::new(value, D::zero()))
+            }
+        )*
+    };
+}
+
+impl<T, D> FromPrimitive for Differential<T, D>
+where
+    T: FromPrimitive,
+    D: Zero,
+{
+    forward_from_primitive!(
+        from_isize(isize), from_i8(i8), from_i16(i16), from_i32(i32), from_i64(i64), from_i128(i128),
+        from_usize(usize), from_u8(u8), from_u16(u16), from_u32(u32), from_u64(u64), from_u128(u128),
+        from_f32(f32), from_f64(f64),
+    );
+}
+
+impl<T, D> Differential<T, D> {
+    /// Casts both the value and the derivative to other numeric types
+    ///
+    /// Vector and nested derivatives are cast component by component, see [`CastTangent`].
+    /// Returns `None` if either of them cannot be represented in the target type.
+    pub fn cast<U, E>(self) -> Option<Differential<U, E>>
+    where
+        T: ToPrimitive,
+        D: CastTangent<E>,
+        U: NumCast,
+    {
+        Some(Differential::new(U::from(self.value)?, self.derivative.cast_tangent()?))
+    }
+}
+
+macro_rules! cast_tangent_primitive {
+    ($($t:ty),*) => {
+        $(
+            impl<E: NumCast> CastTangent<E> for $t {
+                fn cast_tangent(self) -> Option<E> {
+                    E::from(self)
+                }
+            }
+        )*
+    };
+}
+
+cast_tangent_primitive!(f32, f64, i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize);
+
+impl<T, D, U, E> CastTangent<Differential<U, E>> for Differential<T, D>
+where
+    T: ToPrimitive,
+    D: CastTangent<E>,
+    U: NumCast,
+{
+    fn cast_tangent(self) -> Option<Differential<U, E>> {
+        self.cast()
     }
 }
 
-impl<T, D> Real for Differential<T, D>
+macro_rules! piecewise_primitive {
+    ($($t:ty),*) => {
+        $(
+            impl<D> Piecewise<D> for $t {
+                fn is_less(&self, other: &Self) -> Option<bool> {
+                    Some(self < other)
+                }
+
+                fn is_negative_sign(&self) -> Option<bool> {
+                    Some(self.is_sign_negative())
+                }
+
+                fn join(_: Differential<Self, D>, _: Differential<Self, D>) -> Differential<Self, D> {
+                    unreachable!("the comparisons of {} are always decided", stringify!($t))
+                }
+            }
+        )*
+    };
+}
+
+piecewise_primitive!(f32, f64);
+
+impl<T, D> Piecewise<Differential<T, D>> for Differential<T, D>
 where
-    T: Real + NumCast,
-    D: Neg<Output = D> + Zero + Copy + std::ops::Mul<T, Output = D> + std::ops::Div<T, Output = D> + std::ops::Sub<Output = D>, // TODO remove copy
+    T: Piecewise<D>,
+{
+    fn is_less(&self, other: &Self) -> Option<bool> {
+        self.value.is_less(&other.value)
+    }
+
+    fn is_negative_sign(&self) -> Option<bool> {
+        self.value.is_negative_sign()
+    }
+
+    fn join(a: Differential<Self, Self>, b: Differential<Self, Self>) -> Differential<Self, Self> {
+        Differential::new(T::join(a.value, b.value), T::join(a.derivative, b.derivative))
+    }
+}
+
+#[cfg(any(feature = "std", feature = "libm"))]
+impl<T, D> Float for Differential<T, D>
+where
+    T: Float + Piecewise<D>,
+    D: Neg<Output = D> + Zero + Copy + core::ops::Mul<T, Output = D> + core::ops::Div<T, Output = D> + core::ops::Sub<Output = D>, // TODO remove copy
     Self: NumOps,
 {
+    fn nan() -> Self {
+        Self::new(T::nan(), D::zero())
+    }
+
+    fn infinity() -> Self {
+        Self::new(T::infinity(), D::zero())
+    }
+
+    fn neg_infinity() -> Self {
+        Self::new(T::neg_infinity(), D::zero())
+    }
+
+    fn neg_zero() -> Self {
+        Self::new(T::neg_zero(), D::zero())
+    }
+
     fn min_value() -> Self {
         Self::new(T::min_value(), D::zero())
     }
@@ -206,6 +337,30 @@ where
         Self::new(T::max_value(), D::zero())
     }
 
+    fn is_nan(self) -> bool {
+        self.value.is_nan()
+    }
+
+    fn is_infinite(self) -> bool {
+        self.value.is_infinite()
+    }
+
+    fn is_finite(self) -> bool {
+        self.value.is_finite()
+    }
+
+    fn is_normal(self) -> bool {
+        self.value.is_normal()
+    }
+
+    fn is_subnormal(self) -> bool {
+        self.value.is_subnormal()
+    }
+
+    fn classify(self) -> FpCategory {
+        self.value.classify()
+    }
+
     fn floor(self) -> Self {
         Self::new(self.value.floor(), D::zero())
     }
@@ -223,11 +378,15 @@ where
     }
 
     fn fract(self) -> Self {
-        Self::new(self.value.fract(), D::zero())
+        Self::new(self.value.fract(), self.derivative)
     }
 
     fn abs(self) -> Self {
-        Self::new(self.value.abs(), D::zero())
+        match self.value.is_negative_sign() {
+            Some(true) => -self,
+            Some(false) => self,
+            None => Self::new(self.value.abs(), T::join(-self, self).derivative),
+        }
     }
 
     fn signum(self) -> Self {
@@ -242,8 +401,19 @@ where
         self.value.is_sign_negative()
     }
 
-    fn mul_add(self, _a: Self, _b: Self) -> Self {
-        todo!();
+    fn copysign(self, sign: Self) -> Self {
+        match (self.value.is_negative_sign(), sign.value.is_negative_sign()) {
+            (Some(a), Some(b)) if a == b => self,
+            (Some(_), Some(_)) => -self,
+            _ => T::join(self, -self),
+        }
+    }
+
+    fn mul_add(self, a: Self, b: Self) -> Self {
+        Self::new(
+            self.value.mul_add(a.value, b.value),
+            self.derivative * a.value + a.derivative * self.value + b.derivative,
+        )
     }
 
     fn recip(self) -> Self {
@@ -251,79 +421,115 @@ where
     }
 
     fn powi(self, n: i32) -> Self {
-        Self::new(self.value.powi(n), self.derivative * self.value.powi(n - 1) * T::from::<i32>(n).unwrap()) // TODO remove unwrap somehow
-    }
-
-    fn powf(self, _n: Self) -> Self {
-        todo!();
+        if n == 0 {
+            // constant, even at 0 where x^(n - 1) is infinite
+            return Self::new(self.value.powi(0), D::zero());
+        }
+        let previous = if n == i32::MIN { self.value.powi(n) / self.value } else { self.value.powi(n - 1) };
+        Self::new(self.value.powi(n), self.derivative * previous * T::from::<i32>(n).unwrap()) // TODO remove unwrap somehow
+    }
+
+    fn powf(self, n: Self) -> Self {
+        let value = self.value.powf(n.value);
+        let derivative = self.derivative * (n.value * self.value.powf(n.value - T::one()));
+        // d(x^n)/dn = x^n ln(x) is only defined for positive bases
+        let positive = || Self::new(value, derivative + n.derivative * (value * self.value.ln()));
+        match T::zero().is_less(&self.value) {
+            Some(true) => positive(),
+            Some(false) => Self::new(value, derivative),
+            None => T::join(positive(), Self::new(value, derivative)),
+        }
     }
 
     fn sqrt(self) -> Self {
-        Self::new(self.value.sqrt(), self.derivative / (T::from(2).unwrap() * self.value.sqrt())) // TODO copilot, to check
+        Self::new(self.value.sqrt(), self.derivative / (T::from(2).unwrap() * self.value.sqrt()))
     }
 
     fn exp(self) -> Self {
-        Self::new(self.value.exp(), self.derivative * self.value.exp()) // TODO copilot, to check
+        Self::new(self.value.exp(), self.derivative * self.value.exp())
     }
 
     fn exp2(self) -> Self {
-        todo!();
+        let exp2 = self.value.exp2();
+        Self::new(exp2, self.derivative * (exp2 * T::from(2).unwrap().ln()))
     }
 
     fn ln(self) -> Self {
-        Self::new(self.value.ln(), self.derivative / self.value) // TODO copilot, to check
+        Self::new(self.value.ln(), self.derivative / self.value)
     }
 
-    fn log(self, _base: Self) -> Self {
-        todo!();
+    fn log(self, base: Self) -> Self {
+        self.ln() / base.ln()
     }
 
     fn log2(self) -> Self {
-        todo!();
+        Self::new(self.value.log2(), self.derivative / (self.value * T::from(2).unwrap().ln()))
     }
 
     fn log10(self) -> Self {
-        todo!();
+        Self::new(self.value.log10(), self.derivative / (self.value * T::from(10).unwrap().ln()))
     }
 
     fn to_degrees(self) -> Self {
-        todo!();
+        Self::new(self.value.to_degrees(), self.derivative * T::one().to_degrees())
     }
 
     fn to_radians(self) -> Self {
-        todo!();
+        Self::new(self.value.to_radians(), self.derivative * T::one().to_radians())
     }
 
     fn max(self, other: Self) -> Self {
-        if self.value > other.value {
-            self
-        } else {
-            other
+        // a NaN operand yields the other one, as for the primitive floats
+        if other.is_nan() {
+            return self;
+        }
+        if self.is_nan() {
+            return other;
+        }
+        match other.value.is_less(&self.value) {
+            Some(true) => self,
+            Some(false) => other,
+            None => Self::new(self.value.max(other.value), T::join(self, other).derivative),
         }
     }
 
     fn min(self, other: Self) -> Self {
-        if self.value < other.value {
-            self
-        } else {
-            other
+        if other.is_nan() {
+            return self;
+        }
+        if self.is_nan() {
+            return other;
+        }
+        match self.value.is_less(&other.value) {
+            Some(true) => self,
+            Some(false) => other,
+            None => Self::new(self.value.min(other.value), T::join(self, other).derivative),
         }
     }
 
     fn abs_sub(self, other: Self) -> Self {
-        if self.value < other.value {
-            Self::zero()
-        } else {
-            self - other
+        match self.value.is_less(&other.value) {
+            Some(true) => Self::zero(),
+            Some(false) => self - other,
+            None => Self::new(self.value.abs_sub(other.value), T::join(Self::zero(), self - other).derivative),
         }
     }
 
     fn cbrt(self) -> Self {
-        todo!();
+        let cbrt = self.value.cbrt();
+        Self::new(cbrt, self.derivative / (T::from(3).unwrap() * cbrt.powi(2)))
     }
 
-    fn hypot(self, _other: Self) -> Self {
-        todo!();
+    fn hypot(self, other: Self) -> Self {
+        let hypot = self.value.hypot(other.value);
+        if hypot.is_zero() {
+            // not differentiable at the origin, zero is a subgradient
+            return Self::new(hypot, D::zero());
+        }
+        Self::new(
+            hypot,
+            (self.derivative * self.value + other.derivative * other.value) / hypot,
+        )
     }
 
     fn sin(self) -> Self {
@@ -343,35 +549,35 @@ where
     fn tan(self) -> Self {
         Self::new(
             self.value.tan(),
-            self.derivative / self.value.cos().powi(2), // TODO copilot, to check
+            self.derivative / self.value.cos().powi(2),
         )
     }
 
     fn asin(self) -> Self {
         Self::new(
             self.value.asin(),
-            self.derivative / (T::one() - self.value.powi(2)).sqrt(), // TODO copilot, to check
+            self.derivative / (T::one() - self.value.powi(2)).sqrt(),
         )
     }
 
     fn acos(self) -> Self {
         Self::new(
             self.value.acos(),
-            -self.derivative / (T::one() - self.value.powi(2)).sqrt(), // TODO copilot, to check
+            -self.derivative / (T::one() - self.value.powi(2)).sqrt(),
         )
     }
 
     fn atan(self) -> Self {
         Self::new(
             self.value.atan(),
-            self.derivative / (T::one() + self.value.powi(2)), // TODO copilot, to check
+            self.derivative / (T::one() + self.value.powi(2)),
         )
     }
 
     fn atan2(self, other: Self) -> Self {
         Self::new(
             self.value.atan2(other.value),
-            (self.derivative * other.value - other.derivative * self.value) / (self.value.powi(2) + other.value.powi(2)), // TODO copilot, to check
+            (self.derivative * other.value - other.derivative * self.value) / (self.value.powi(2) + other.value.powi(2)),
         )
     }
 
@@ -384,52 +590,216 @@ where
     }
 
     fn exp_m1(self) -> Self {
-        self.exp() - Self::one()
+        Self::new(self.value.exp_m1(), self.derivative * self.value.exp())
     }
 
     fn ln_1p(self) -> Self {
-        todo!();
+        Self::new(self.value.ln_1p(), self.derivative / (T::one() + self.value))
     }
 
     fn sinh(self) -> Self {
         Self::new(
             self.value.sinh(),
-            self.derivative * self.value.cosh(), // TODO copilot, to check
+            self.derivative * self.value.cosh(),
         )
     }
 
     fn cosh(self) -> Self {
         Self::new(
             self.value.cosh(),
-            self.derivative * self.value.sinh(), // TODO copilot, to check
+            self.derivative * self.value.sinh(),
         )
     }
 
     fn tanh(self) -> Self {
         Self::new(
             self.value.tanh(),
-            self.derivative / self.value.cosh().powi(2), // TODO copilot, to check
+            self.derivative / self.value.cosh().powi(2),
         )
     }
 
     fn asinh(self) -> Self {
         Self::new(
             self.value.asinh(),
-            self.derivative / (self.value.powi(2) + T::one()).sqrt(), // TODO copilot, to check
+            self.derivative / (self.value.powi(2) + T::one()).sqrt(),
         )
     }
 
     fn acosh(self) -> Self {
         Self::new(
             self.value.acosh(),
-            self.derivative / (self.value.powi(2) - T::one()).sqrt(), // TODO copilot, to check
+            self.derivative / (self.value.powi(2) - T::one()).sqrt(),
         )
     }
 
     fn atanh(self) -> Self {
         Self::new(
             self.value.atanh(),
-            self.derivative / (T::one() - self.value.powi(2)), // TODO copilot, to check
+            self.derivative / (T::one() - self.value.powi(2)),
         )
     }
-}
\ No newline at end of file
+
+    fn integer_decode(self) -> (u64, i16, i8) {
+        self.value.integer_decode()
+    }
+}
+
+macro_rules! float_consts {
+    ($($name:ident),* $(,)?) => {
+        $(
+            fn $name() -> Self {
+                Self::new(T::$name(), D::zero())
+            }
+        )*
+    };
+}
+
+impl<T, D> FloatConst for Differential<T, D>
+where
+    T: FloatConst + core::ops::Add<Output = T> + core::ops::Div<Output = T>,
+    D: Zero,
+{
+    float_consts!(
+        E, FRAC_1_PI, FRAC_1_SQRT_2, FRAC_2_PI, FRAC_2_SQRT_PI, FRAC_PI_2, FRAC_PI_3, FRAC_PI_4,
+        FRAC_PI_6, FRAC_PI_8, LN_10, LN_2, LOG10_E, LOG2_E, PI, SQRT_2, TAU, LOG10_2, LOG2_10,
+    );
+}
+
+#[cfg(all(test, any(feature = "std", feature = "libm")))]
+mod float_tests {
+    use super::*;
+
+    #[test]
+    fn float_constants() {
+        type D = Differential<f64>;
+        assert!(D::nan().is_nan());
+        assert!(D::infinity().is_infinite() && !D::infinity().is_finite());
+        assert_eq!(D::PI(), D::new(core::f64::consts::PI, 0.0));
+        assert_eq!(D::PI().derivative, 0.0);
+        assert_eq!(D::TAU().derivative, 0.0);
+        assert_eq!(D::new(1.5, 1.0).classify(), FpCategory::Normal);
+    }
+
+    #[test]
+    fn float_copysign() {
+        type D = Differential<f64>;
+        let x = D::new(2.0, 3.0);
+        assert_eq!(x.copysign(D::new(-1.0, 5.0)).value, -2.0);
+        assert_eq!(x.copysign(D::new(-1.0, 5.0)).derivative, -3.0);
+        assert_eq!(x.copysign(D::new(1.0, 5.0)).derivative, 3.0);
+        assert_eq!((-x).abs().derivative, 3.0);
+    }
+
+    #[test]
+    fn float_derivatives() {
+        type Case = (fn(Differential) -> Differential, fn(f64) -> f64);
+        let cases: [Case; 9] = [
+            (|x| x.exp2(), |x| x.exp2()),
+            (|x| x.log2(), |x| x.log2()),
+            (|x| x.log10(), |x| x.log10()),
+            (|x| x.cbrt(), |x| x.cbrt()),
+            (|x| x.ln_1p(), |x| x.ln_1p()),
+            (|x| x.exp_m1(), |x| x.exp_m1()),
+            (|x| x.to_degrees(), |x| x.to_degrees()),
+            (|x| x.powf(x), |x| x.powf(x)),
+            (|x| x.hypot(x * x), |x| x.hypot(x * x)),
+        ];
+        let x = 0.7;
+        let h = 1e-6;
+        for (f, g) in cases {
+            let expected = (g(x + h) - g(x - h)) / (2.0 * h);
+            let derivative = f(Differential::new(x, 1.0)).derivative;
+            assert!((derivative - expected).abs() < 1e-6 * expected.abs().max(1.0), "{} != {}", derivative, expected);
+        }
+    }
+
+    #[test]
+    fn float_max_min_with_nan() {
+        type D = Differential<f64>;
+        let x = D::new(2.0, 3.0);
+        for y in [x.max(D::nan()), D::nan().max(x), x.min(D::nan()), D::nan().min(x)] {
+            assert_eq!(y, x);
+        }
+        assert!(D::nan().max(D::nan()).is_nan());
+        assert_eq!(x.max(D::new(1.0, 5.0)), x);
+        assert_eq!(x.min(D::new(1.0, 5.0)), D::new(1.0, 5.0));
+    }
+
+    #[test]
+    fn float_powi_at_zero() {
+        type D = Differential<f64>;
+        assert_eq!(D::new(0.0, 1.0).powi(0), D::new(1.0, 0.0));
+        assert_eq!(D::new(3.0, 1.0).powi(0), D::new(1.0, 0.0));
+        assert_eq!(D::new(0.0, 1.0).powi(1), D::new(0.0, 1.0));
+        assert_eq!(D::new(0.0, 1.0).powi(2), D::new(0.0, 0.0));
+        assert_eq!(D::new(1.0, 1.0).powi(i32::MIN), D::new(1.0, i32::MIN as f64));
+    }
+
+    #[test]
+    fn float_hypot_at_origin() {
+        type D = Differential<f64>;
+        assert_eq!(D::new(0.0, 1.0).hypot(D::new(0.0, 2.0)), D::new(0.0, 0.0));
+        assert_eq!(D::new(3.0, 1.0).hypot(D::new(4.0, 0.0)), D::new(5.0, 0.6));
+    }
+
+    #[test]
+    fn generic_float_code() {
+        fn logistic<F: Float>(x: F) -> F {
+            F::one() / (F::one() + (-x).exp())
+        }
+        let y = logistic(Differential::new(0.0, 1.0));
+        assert_eq!(y.value, 0.5);
+        assert_eq!(y.derivative, 0.25);
+    }
+
+    #[cfg(feature = "nalgebra")]
+    #[test]
+    fn float_for_tangents_without_text_form() {
+        use ::nalgebra::Matrix2;
+
+        fn square<F: Float + Signed>(x: F) -> F {
+            x.abs().powi(2)
+        }
+        let x = Differential::new(-3.0, Matrix2::new(1.0, 2.0, 3.0, 4.0));
+        assert_eq!(square(x).derivative, Matrix2::new(-6.0, -12.0, -18.0, -24.0));
+    }
+}
+
+#[cfg(test)]
+mod cast_tests {
+    use super::*;
+
+    #[test]
+    fn casts_without_precision_loss() {
+        let big = i64::MAX - 1;
+        assert_eq!(<Differential<i64> as NumCast>::from(big).unwrap().value, big);
+        assert_eq!(<Differential<u128> as NumCast>::from(u128::MAX).unwrap().value, u128::MAX);
+        assert_eq!(Differential::<u64>::from_u64(u64::MAX).unwrap().value, u64::MAX);
+        assert!(<Differential<u8> as NumCast>::from(300).is_none());
+        assert!(<Differential<u8> as NumCast>::from(f64::NAN).is_none());
+        assert_eq!(Differential::new(i128::MAX, 0).to_i128(), Some(i128::MAX));
+        assert_eq!(Differential::new(-1.5, 2.0).to_u32(), None);
+    }
+
+    #[test]
+    fn cast_keeps_derivative() {
+        let x = Differential::new(1.5f64, 2.5f64);
+        assert_eq!(x.cast::<f32, f32>(), Some(Differential::new(1.5f32, 2.5f32)));
+        assert_eq!(x.cast::<f32, f32>().unwrap().derivative, 2.5);
+        assert_eq!(Differential::new(1.0, -3.0).cast::<f64, u8>(), None);
+        let nested = Differential::new(1.0f64, Differential::new(2.0f64, 3.0f64));
+        assert_eq!(nested.cast::<f32, Differential<f32>>(), Some(Differential::new(1.0f32, Differential::new(2.0f32, 3.0f32))));
+    }
+
+    #[cfg(feature = "nalgebra")]
+    #[test]
+    fn cast_vector_derivative() {
+        use ::nalgebra::{DVector, Vector3};
+
+        let gradient = Differential::new(1.5f64, Vector3::new(1.0f64, -2.0, 0.25));
+        assert_eq!(gradient.cast::<f32, Vector3<f32>>(), Some(Differential::new(1.5f32, Vector3::new(1.0f32, -2.0, 0.25))));
+        assert_eq!(gradient.cast::<f64, Vector3<u8>>(), None);
+        let dynamic = Differential::new(1i32, DVector::from_vec(vec![1i64, 2, 3]));
+        assert_eq!(dynamic.cast::<f64, DVector<f64>>().unwrap().derivative, DVector::from_vec(vec![1.0, 2.0, 3.0]));
+    }
+}
@@ -1,7 +1,7 @@
 
-use std::ops::{Div, Mul, Sub};
+use core::ops::{Div, Mul, Sub};
 
-use num_traits::Zero;
+use num_traits::{One, Zero};
 
 use super::*;
 
@@ -18,15 +18,15 @@ impl<T, D> PartialOrd for Differential<T, D>
 where
     T: PartialOrd,
 {
-    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+    fn partial_cmp(&self, other: &Self) -> Option<core::cmp::Ordering> {
         self.value.partial_cmp(&other.value)
     }
 }
 
-impl<T, D> std::ops::Add for Differential<T, D>
+impl<T, D> core::ops::Add for Differential<T, D>
 where
-    T: std::ops::Add<Output = T>,
-    D: std::ops::Add<Output = D>,
+    T: core::ops::Add<Output = T>,
+    D: core::ops::Add<Output = D>,
 {
     type Output = Self;
 
@@ -38,9 +38,9 @@ where
     }
 }
 
-impl<T, D> std::ops::Add<Differential<T, D>> for f64
+impl<T, D> core::ops::Add<Differential<T, D>> for f64
 where
-    Differential<T, D>: std::ops::Add<Output = Differential<T, D>>,
+    Differential<T, D>: core::ops::Add<Output = Differential<T, D>>,
     T: From<f64>,
     D: Zero,
 {
@@ -51,10 +51,10 @@ where
     }
 }
 
-impl<T, D> std::ops::AddAssign for Differential<T, D>
+impl<T, D> core::ops::AddAssign for Differential<T, D>
 where
-    T: std::ops::AddAssign,
-    D: std::ops::AddAssign,
+    T: core::ops::AddAssign,
+    D: core::ops::AddAssign,
 {
     fn add_assign(&mut self, other: Self) {
         self.value += other.value;
@@ -62,10 +62,10 @@ where
     }
 }
 
-impl<T, D> std::ops::Neg for Differential<T, D>
+impl<T, D> core::ops::Neg for Differential<T, D>
 where
-    T: std::ops::Neg<Output = T>,
-    D: std::ops::Neg<Output = D>,
+    T: core::ops::Neg<Output = T>,
+    D: core::ops::Neg<Output = D>,
 {
     type Output = Self;
 
@@ -77,10 +77,10 @@ where
     }
 }
 
-impl<T, D> std::ops::Sub for Differential<T, D>
+impl<T, D> core::ops::Sub for Differential<T, D>
 where
-    T: std::ops::Sub<Output = T>,
-    D: std::ops::Sub<Output = D>,
+    T: core::ops::Sub<Output = T>,
+    D: core::ops::Sub<Output = D>,
 {
     type Output = Self;
 
@@ -92,9 +92,9 @@ where
     }
 }
 
-impl<T, D> std::ops::Sub<Differential<T, D>> for f64
+impl<T, D> core::ops::Sub<Differential<T, D>> for f64
 where
-    Differential<T, D>: std::ops::Sub<Output = Differential<T, D>>,
+    Differential<T, D>: core::ops::Sub<Output = Differential<T, D>>,
     T: From<f64>,
     D: Zero,
 {
@@ -105,10 +105,10 @@ where
     }
 }
 
-impl<T, D> std::ops::SubAssign for Differential<T, D>
+impl<T, D> core::ops::SubAssign for Differential<T, D>
 where
-    T: std::ops::SubAssign,
-    D: std::ops::SubAssign,
+    T: core::ops::SubAssign,
+    D: core::ops::SubAssign,
 {
     fn sub_assign(&mut self, other: Self) {
         self.value -= other.value;
@@ -116,10 +116,10 @@ where
     }
 }
 
-impl<T, D> std::ops::Mul for Differential<T, D>
+impl<T, D> core::ops::Mul for Differential<T, D>
 where
-    T: std::ops::Mul<Output = T> + Clone,
-    D: std::ops::Mul<T, Output = D> + std::ops::Add<Output = D> + Clone,
+    T: core::ops::Mul<Output = T> + Clone,
+    D: core::ops::Mul<T, Output = D> + core::ops::Add<Output = D> + Clone,
 {
     type Output = Self;
 
@@ -131,10 +131,10 @@ where
     }
 }
 
-impl<T, D> std::ops::Mul<T> for Differential<T, D>
+impl<T, D> core::ops::Mul<T> for Differential<T, D>
 where
-    T: std::ops::Mul<Output = T> + Clone,
-    D: std::ops::Mul<T, Output = D>,
+    T: core::ops::Mul<Output = T> + Clone,
+    D: core::ops::Mul<T, Output = D>,
 {
     type Output = Self;
     fn mul(self, rhs: T) -> Self::Output {
@@ -145,16 +145,16 @@ where
     }
 }
 
-impl<T, D> std::ops::MulAssign for Differential<T, D>
+impl<T, D> core::ops::MulAssign for Differential<T, D>
 where
-    Self: std::ops::Mul<Output = Self> + Clone, // TODO without Clone
+    Self: core::ops::Mul<Output = Self> + Clone, // TODO without Clone
 {
     fn mul_assign(&mut self, other: Self) {
         *self = self.clone() * other;
     }
 }
 
-impl<T, D> std::ops::Div for Differential<T, D>
+impl<T, D> core::ops::Div for Differential<T, D>
 where
     T: Div<Output = T> + Mul<Output = T> + Sub<Output = T> + Clone, // TODO without Clone
     D: Zero + Mul<T, Output = D> + Sub<Output = D> + Div<T, Output = D>,
@@ -169,19 +169,19 @@ where
     }
 }
 
-impl<T, D> std::ops::DivAssign for Differential<T, D>
+impl<T, D> core::ops::DivAssign for Differential<T, D>
 where
-    Self: std::ops::Div<Output = Self> + Clone, // TODO without Clone
+    Self: core::ops::Div<Output = Self> + Clone, // TODO without Clone
 {
     fn div_assign(&mut self, other: Self) {
         *self = self.clone() / other;
     }
 }
 
-impl<T, D> std::ops::Rem for Differential<T, D>
+impl<T, D> core::ops::Rem for Differential<T, D>
 where
-    T: std::ops::Rem<Output = T> + Div<Output = T> + Sub<Output = T> + Clone,
-    D: std::ops::Mul<T, Output = D> + std::ops::Sub<Output = D>,
+    T: core::ops::Rem<Output = T> + Div<Output = T> + Sub<Output = T> + Clone,
+    D: core::ops::Mul<T, Output = D> + core::ops::Sub<Output = D>,
 {
     type Output = Self;
 
@@ -196,11 +196,47 @@ where
     }
 }
 
-impl<T, D> std::ops::RemAssign for Differential<T, D>
+impl<T, D> core::ops::RemAssign for Differential<T, D>
 where
-    Self: std::ops::Rem<Output = Self> + Clone, // TODO without Clone
+    Self: core::ops::Rem<Output = Self> + Clone, // TODO without Clone
 {
     fn rem_assign(&mut self, _other: Self) {
         *self = self.clone() % _other;
     }
-}
\ No newline at end of file
+}
+
+impl<T, D> core::iter::Sum for Differential<T, D>
+where
+    Self: Zero,
+{
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Self::zero(), |acc, x| acc + x)
+    }
+}
+
+impl<'a, T, D> core::iter::Sum<&'a Differential<T, D>> for Differential<T, D>
+where
+    Self: Zero + Clone,
+{
+    fn sum<I: Iterator<Item = &'a Self>>(iter: I) -> Self {
+        iter.fold(Self::zero(), |acc, x| acc + x.clone())
+    }
+}
+
+impl<T, D> core::iter::Product for Differential<T, D>
+where
+    Self: One,
+{
+    fn product<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Self::one(), |acc, x| acc * x)
+    }
+}
+
+impl<'a, T, D> core::iter::Product<&'a Differential<T, D>> for Differential<T, D>
+where
+    Self: One + Clone,
+{
+    fn product<I: Iterator<Item = &'a Self>>(iter: I) -> Self {
+        iter.fold(Self::one(), |acc, x| acc * x.clone())
+    }
+}
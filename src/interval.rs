@@ -0,0 +1,397 @@
+/*!
+Interval arithmetic with outward rounding.
+
+[`Interval`] satisfies the bounds required by [`Differential`], so
+`Differential<Interval, Interval>` evaluated on an interval `X` encloses both `f(X)` and `f'(X)`.
+This is used by [`interval_newton`] for validated root isolation. Piecewise functions such as `abs`
+or `max` enclose both branches when the interval does not decide between them, see
+[`Piecewise`](crate::Piecewise).
+*/
+
+use num_traits::Float;
+
+#[cfg(feature = "std")]
+use crate::Differential;
+
+mod ops;
+mod num_traits_impl;
+
+/// A closed interval `[lo, hi]` of `f64` values
+///
+/// All operations round outward, so the result always encloses the exact result for every
+/// point of the operands. Basic arithmetic and `sqrt` are assumed to be correctly rounded, the
+/// other elementary functions are assumed to be accurate within [`ELEMENTARY_ULPS`] ulps.
+///
+/// An interval with `NaN` bounds represents an empty (undefined) result, for example the
+/// square root of a negative interval.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Interval {
+    lo: f64,
+    hi: f64,
+}
+
+/// The accuracy (in ulps) assumed for the elementary functions of the platform
+pub const ELEMENTARY_ULPS: u32 = 2;
+
+impl Interval {
+    /// The empty interval
+    pub const EMPTY: Interval = Interval { lo: f64::NAN, hi: f64::NAN };
+
+    /// The whole real line
+    pub const ENTIRE: Interval = Interval { lo: f64::NEG_INFINITY, hi: f64::INFINITY };
+
+    /// An enclosure of π
+    pub const PI: Interval = Interval { lo: core::f64::consts::PI, hi: 3.1415926535897936 };
+
+    /// Creates a new interval `[lo, hi]`
+    ///
+    /// # Panics
+    /// Panics if `lo > hi` or if a bound is `NaN`.
+    pub fn new(lo: f64, hi: f64) -> Self {
+        assert!(lo <= hi, "invalid interval [{}, {}]", lo, hi);
+        Self { lo, hi }
+    }
+
+    /// Creates the degenerate interval `[x, x]`
+    pub fn point(x: f64) -> Self {
+        Self { lo: x, hi: x }
+    }
+
+    /// The lower bound
+    pub fn lo(&self) -> f64 {
+        self.lo
+    }
+
+    /// The upper bound
+    pub fn hi(&self) -> f64 {
+        self.hi
+    }
+
+    /// The midpoint of the interval
+    pub fn mid(&self) -> f64 {
+        if self.lo.is_infinite() || self.hi.is_infinite() {
+            // finite representative of unbounded intervals
+            return (self.lo.max(f64::MIN) / 2.0 + self.hi.min(f64::MAX) / 2.0).clamp(self.lo, self.hi);
+        }
+        let mid = self.lo + (self.hi - self.lo) / 2.0;
+        mid.clamp(self.lo, self.hi)
+    }
+
+    /// The width `hi - lo`, rounded up
+    pub fn width(&self) -> f64 {
+        (self.hi - self.lo).next_up()
+    }
+
+    /// Whether the interval is empty
+    pub fn is_empty(&self) -> bool {
+        self.lo.is_nan() || self.hi.is_nan()
+    }
+
+    /// Whether the interval contains `x`
+    pub fn contains(&self, x: f64) -> bool {
+        self.lo <= x && x <= self.hi
+    }
+
+    /// Whether `self` is contained in the interior of `other`
+    pub fn is_interior_of(&self, other: &Self) -> bool {
+        other.lo < self.lo && self.hi < other.hi
+    }
+
+    /// The intersection of two intervals, `None` if they are disjoint
+    pub fn intersection(&self, other: &Self) -> Option<Self> {
+        let lo = self.lo.max(other.lo);
+        let hi = self.hi.min(other.hi);
+        if lo <= hi {
+            Some(Self { lo, hi })
+        } else {
+            None
+        }
+    }
+
+    /// The smallest interval containing both intervals
+    pub fn hull(&self, other: &Self) -> Self {
+        if self.is_empty() {
+            return *other;
+        }
+        if other.is_empty() {
+            return *self;
+        }
+        Self { lo: self.lo.min(other.lo), hi: self.hi.max(other.hi) }
+    }
+
+    /// Splits the interval at its midpoint
+    pub fn bisect(&self) -> (Self, Self) {
+        let mid = self.mid();
+        (Self { lo: self.lo, hi: mid }, Self { lo: mid, hi: self.hi })
+    }
+
+    /// Builds an interval from bounds computed in round-to-nearest, widening them by `ulps`
+    fn outward(lo: f64, hi: f64, ulps: u32) -> Self {
+        if lo.is_nan() || hi.is_nan() {
+            return Self::EMPTY;
+        }
+        let (mut lo, mut hi) = (lo, hi);
+        for _ in 0..ulps {
+            lo = lo.next_down();
+            hi = hi.next_up();
+        }
+        Self { lo, hi }
+    }
+
+    /// Applies a non-decreasing function to the bounds
+    fn increasing(self, f: impl Fn(f64) -> f64, ulps: u32) -> Self {
+        if self.is_empty() {
+            return Self::EMPTY;
+        }
+        Self::outward(f(self.lo), f(self.hi), ulps)
+    }
+
+    /// Applies a non-increasing function to the bounds
+    fn decreasing(self, f: impl Fn(f64) -> f64, ulps: u32) -> Self {
+        if self.is_empty() {
+            return Self::EMPTY;
+        }
+        Self::outward(f(self.hi), f(self.lo), ulps)
+    }
+
+    /// Restricts the interval to the domain `[lo, hi]` of a function
+    fn restrict(self, lo: f64, hi: f64) -> Self {
+        self.intersection(&Self { lo, hi }).unwrap_or(Self::EMPTY)
+    }
+
+    /// Clamps the bounds to the range `[lo, hi]` of a function
+    fn clamp(self, lo: f64, hi: f64) -> Self {
+        if self.is_empty() {
+            return self;
+        }
+        Self { lo: self.lo.clamp(lo, hi), hi: self.hi.clamp(lo, hi) }
+    }
+
+    /// Whether `self` may contain an integer with the given parity (`0` even, `1` odd)
+    fn may_contain_integer(self, parity: i64) -> bool {
+        if self.is_empty() {
+            return false;
+        }
+        let first = Float::ceil(self.lo);
+        if first > self.hi {
+            return false;
+        }
+        if self.hi - first >= 1.0 || first.abs() >= 9007199254740992.0 {
+            return true;
+        }
+        (first as i64).rem_euclid(2) == parity
+    }
+}
+
+macro_rules! fmt_interval {
+    ($($trait:ident),*) => {
+        $(
+            impl core::fmt::$trait for Interval {
+                fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+                    write!(f, "[")?;
+                    core::fmt::$trait::fmt(&self.lo, f)?;
+                    write!(f, ", ")?;
+                    core::fmt::$trait::fmt(&self.hi, f)?;
+                    write!(f, "]")
+                }
+            }
+        )*
+    };
+}
+
+fmt_interval!(Display, LowerExp, UpperExp);
+
+impl From<f64> for Interval {
+    fn from(x: f64) -> Self {
+        Self::point(x)
+    }
+}
+
+/// Options for [`interval_newton`]
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, PartialEq)]
+pub struct IntervalNewtonOptions {
+    /// Enclosures narrower than this are reported
+    pub tolerance: f64,
+
+    /// Maximum number of boxes processed before giving up
+    pub max_iterations: usize,
+}
+
+#[cfg(feature = "std")]
+impl Default for IntervalNewtonOptions {
+    fn default() -> Self {
+        Self {
+            tolerance: 1e-12,
+            max_iterations: 10_000,
+        }
+    }
+}
+
+/// A validated enclosure of a root
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RootEnclosure {
+    /// The interval enclosing the root(s)
+    pub interval: Interval,
+
+    /// If `true`, the interval provably contains exactly one root.
+    ///
+    /// If `false`, the interval may contain zero, one or more roots.
+    pub unique: bool,
+}
+
+/// Isolates all the roots of `f` in `domain` with the interval Newton method
+///
+/// `f` is evaluated on `Differential<Interval, Interval>` to obtain enclosures of both the function
+/// and its derivative. Every root in `domain` is contained in one of the returned enclosures.
+#[cfg(feature = "std")]
+pub fn interval_newton(
+    f: impl Fn(Differential<Interval, Interval>) -> Differential<Interval, Interval>,
+    domain: Interval,
+    options: &IntervalNewtonOptions,
+) -> Vec<RootEnclosure> {
+    let mut roots = Vec::new();
+    let mut boxes = vec![(domain, false)];
+    let mut iterations = 0;
+
+    while let Some((x, unique)) = boxes.pop() {
+        if iterations >= options.max_iterations {
+            roots.push(RootEnclosure { interval: x, unique });
+            continue;
+        }
+        iterations += 1;
+
+        let fx = f(Differential::new(x, Interval::point(1.0)));
+        if !fx.value.contains(0.0) && !fx.value.is_empty() {
+            continue;
+        }
+
+        if x.width() <= options.tolerance {
+            roots.push(RootEnclosure { interval: x, unique });
+            continue;
+        }
+
+        let derivative = fx.derivative;
+        if derivative.is_empty() || derivative.contains(0.0) {
+            let (left, right) = x.bisect();
+            boxes.push((right, false));
+            boxes.push((left, false));
+            continue;
+        }
+
+        // Newton operator N(X) = m - f(m) / F'(X)
+        let m = x.mid();
+        let fm = f(Differential::new(Interval::point(m), Interval::point(0.0))).value;
+        let newton = Interval::point(m) - fm / derivative;
+        let Some(next) = newton.intersection(&x) else {
+            continue;
+        };
+        let unique = unique || newton.is_interior_of(&x);
+
+        if next.width() > 0.5 * x.width() {
+            let (left, right) = next.bisect();
+            boxes.push((right, false));
+            boxes.push((left, false));
+        } else {
+            boxes.push((next, unique));
+        }
+    }
+
+    roots.sort_by(|a, b| a.interval.lo.total_cmp(&b.interval.lo));
+    roots
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn arithmetic_encloses() {
+        let a = Interval::new(1.0, 2.0);
+        let b = Interval::new(-3.0, 0.5);
+        assert_eq!(a + b, Interval::new((-2.0f64).next_down(), 2.5f64.next_up()));
+        assert!((a * b).contains(-6.0) && (a * b).contains(1.0));
+        assert!((Interval::point(1.0) / Interval::point(3.0)).contains(1.0 / 3.0));
+        assert_eq!(a / b, Interval::ENTIRE);
+        assert!(Interval::point(0.1) + Interval::point(0.2) != Interval::point(0.1 + 0.2));
+    }
+
+    #[test]
+    fn elementary_functions_enclose() {
+        let x = Interval::new(-1.0, 4.0);
+        let sin = x.sin();
+        assert!(sin.hi() >= 1.0 && sin.lo() <= (-1.0f64).sin() && sin.lo() >= -1.0);
+        let cos = x.cos();
+        assert!(cos.hi() >= 1.0 && cos.lo() <= -1.0);
+        assert!(x.powi(2).lo() <= 0.0 && x.powi(2).hi() >= 16.0);
+        assert!(x.sqrt().contains(0.0) && x.sqrt().contains(2.0));
+        assert!(Interval::new(-2.0, -1.0).sqrt().is_empty());
+    }
+
+    #[test]
+    fn negative_powers_enclose() {
+        let cube = Interval::new(2.0, 4.0).powi(-3);
+        assert!(cube.contains(1.0 / 8.0) && cube.contains(1.0 / 64.0) && cube.lo() > 0.0);
+        // the magnitude of i32::MIN does not fit in an i32
+        assert!(Interval::point(1.0).powi(i32::MIN).contains(1.0));
+        let tiny = Interval::new(0.5, 1.0).powi(i32::MIN);
+        assert!(tiny.contains(1.0) && tiny.hi() == f64::INFINITY);
+    }
+
+    #[test]
+    fn differential_encloses_derivative() {
+        let x = Differential::new(Interval::new(1.0, 2.0), Interval::point(1.0));
+        let y = x * x + x.sin();
+        // f'(x) = 2x + cos(x) over [1, 2]
+        assert!(y.derivative.contains(2.0 + 1.0f64.cos()));
+        assert!(y.derivative.contains(4.0 + 2.0f64.cos()));
+        assert!(y.value.contains(1.0 + 1.0f64.sin()));
+    }
+
+    #[test]
+    fn piecewise_functions_enclose_both_branches() {
+        let x = Differential::new(Interval::new(-1.0, 2.0), Interval::point(1.0));
+        let abs = x.abs();
+        assert_eq!(abs.value, Interval::new(0.0, 2.0));
+        assert_eq!(abs.derivative, Interval::new(-1.0, 1.0));
+        // decided branches keep the exact derivative
+        assert_eq!(Differential::new(Interval::new(-3.0, -2.0), Interval::point(1.0)).abs().derivative, Interval::point(-1.0));
+
+        let y = Differential::new(Interval::new(0.0, 1.0), Interval::point(0.0));
+        for z in [x.max(y), x.min(y)] {
+            assert!(z.derivative.contains(0.0) && z.derivative.contains(1.0));
+        }
+        assert_eq!(x.max(Differential::new(Interval::new(3.0, 4.0), Interval::point(0.0))).derivative, Interval::point(0.0));
+        assert!(x.copysign(Differential::from(Interval::point(-1.0))).derivative.contains(-1.0));
+        assert!(x.copysign(Differential::from(Interval::point(-1.0))).derivative.contains(1.0));
+
+        // the derivative of x^n with respect to n only exists for x > 0
+        let n = Differential::new(Interval::point(2.0), Interval::point(1.0));
+        let base = Differential::from(Interval::new(-1.0, 2.0));
+        assert!(base.powf(n).derivative.contains(0.0) && base.powf(n).derivative.contains(4.0 * 2.0f64.ln()));
+    }
+
+    #[test]
+    fn newton_keeps_roots_of_piecewise_functions() {
+        let roots = interval_newton(|x| x.abs() - Differential::from(Interval::point(0.5)), Interval::new(-1.0, 1.0), &Default::default());
+        for root in [-0.5, 0.5] {
+            assert!(roots.iter().any(|r| r.interval.contains(root)), "{} is missing from {:?}", root, roots);
+        }
+    }
+
+    #[test]
+    fn newton_isolates_roots() {
+        let roots = interval_newton(|x| x * x - Differential::from(Interval::point(2.0)), Interval::new(-3.0, 3.0), &Default::default());
+        assert_eq!(roots.len(), 2);
+        assert!(roots.iter().all(|r| r.unique));
+        assert!(roots[0].interval.contains(-2.0f64.sqrt()));
+        assert!(roots[1].interval.contains(2.0f64.sqrt()));
+
+        let roots = interval_newton(|x| x.cos(), Interval::new(0.0, 10.0), &Default::default());
+        assert_eq!(roots.len(), 3);
+        for (root, k) in roots.iter().zip([0.5, 1.5, 2.5]) {
+            assert!(root.unique && root.interval.contains(k * core::f64::consts::PI));
+        }
+    }
+}
@@ -0,0 +1,411 @@
+use num_traits::{Float, Num, NumCast, One, ToPrimitive, Zero};
+
+use core::num::FpCategory;
+
+use super::*;
+
+const ULPS: u32 = ELEMENTARY_ULPS;
+
+impl Zero for Interval {
+    fn zero() -> Self {
+        Self::point(0.0)
+    }
+
+    fn is_zero(&self) -> bool {
+        self.lo == 0.0 && self.hi == 0.0
+    }
+}
+
+impl One for Interval {
+    fn one() -> Self {
+        Self::point(1.0)
+    }
+}
+
+impl Num for Interval {
+    type FromStrRadixErr = num_traits::ParseFloatError;
+
+    /// Parses a number or a pair `[lo, hi]`, the result encloses the exact decimal values
+    fn from_str_radix(str: &str, radix: u32) -> Result<Self, Self::FromStrRadixErr> {
+        let invalid = || num_traits::ParseFloatError { kind: num_traits::FloatErrorKind::Invalid };
+        let str = str.trim();
+        match str.strip_prefix('[').and_then(|s| s.strip_suffix(']')) {
+            Some(bounds) => {
+                let (lo, hi) = bounds.split_once(',').ok_or_else(invalid)?;
+                let lo = f64::from_str_radix(lo.trim(), radix)?;
+                let hi = f64::from_str_radix(hi.trim(), radix)?;
+                if lo.is_nan() || hi.is_nan() || lo > hi {
+                    return Err(invalid());
+                }
+                Ok(Self::outward(lo, hi, 1))
+            }
+            None => {
+                let x = f64::from_str_radix(str, radix)?;
+                Ok(Self::outward(x, x, 1))
+            }
+        }
+    }
+}
+
+impl ToPrimitive for Interval {
+    fn to_i64(&self) -> Option<i64> {
+        self.mid().to_i64()
+    }
+
+    fn to_u64(&self) -> Option<u64> {
+        self.mid().to_u64()
+    }
+
+    fn to_f64(&self) -> Option<f64> {
+        Some(self.mid())
+    }
+}
+
+impl NumCast for Interval {
+    fn from<T: ToPrimitive>(n: T) -> Option<Self> {
+        let x = n.to_f64()?;
+        // integers may not be exactly representable
+        let exact = match (n.to_i128(), n.to_u128()) {
+            (Some(i), _) => x as i128 == i,
+            (None, Some(u)) => x as u128 == u,
+            (None, None) => true,
+        };
+        Some(if exact { Self::point(x) } else { Self::outward(x, x, 1) })
+    }
+}
+
+impl Float for Interval {
+    fn nan() -> Self {
+        Self::EMPTY
+    }
+
+    fn infinity() -> Self {
+        Self::point(f64::INFINITY)
+    }
+
+    fn neg_infinity() -> Self {
+        Self::point(f64::NEG_INFINITY)
+    }
+
+    fn neg_zero() -> Self {
+        Self::point(-0.0)
+    }
+
+    fn min_value() -> Self {
+        Self::point(f64::MIN)
+    }
+
+    fn min_positive_value() -> Self {
+        Self::point(f64::MIN_POSITIVE)
+    }
+
+    fn epsilon() -> Self {
+        Self::point(f64::EPSILON)
+    }
+
+    fn max_value() -> Self {
+        Self::point(f64::MAX)
+    }
+
+    fn is_nan(self) -> bool {
+        self.is_empty()
+    }
+
+    fn is_infinite(self) -> bool {
+        self.lo.is_infinite() || self.hi.is_infinite()
+    }
+
+    fn is_finite(self) -> bool {
+        self.lo.is_finite() && self.hi.is_finite()
+    }
+
+    fn is_normal(self) -> bool {
+        self.mid().is_normal()
+    }
+
+    /// The category of the midpoint
+    fn classify(self) -> FpCategory {
+        self.mid().classify()
+    }
+
+    fn floor(self) -> Self {
+        self.increasing(Float::floor, 0)
+    }
+
+    fn ceil(self) -> Self {
+        self.increasing(Float::ceil, 0)
+    }
+
+    fn round(self) -> Self {
+        self.increasing(Float::round, 0)
+    }
+
+    fn trunc(self) -> Self {
+        self.increasing(Float::trunc, 0)
+    }
+
+    fn fract(self) -> Self {
+        if self.is_empty() {
+            return Self::EMPTY;
+        }
+        if self.lo.trunc() == self.hi.trunc() {
+            self.increasing(Float::fract, 0)
+        } else if self.lo >= 0.0 {
+            Self::new(0.0, 1.0)
+        } else if self.hi <= 0.0 {
+            Self::new(-1.0, 0.0)
+        } else {
+            Self::new(-1.0, 1.0)
+        }
+    }
+
+    fn abs(self) -> Self {
+        if self.is_empty() || self.lo >= 0.0 {
+            self
+        } else if self.hi <= 0.0 {
+            -self
+        } else {
+            Self::new(0.0, self.hi.max(-self.lo))
+        }
+    }
+
+    fn signum(self) -> Self {
+        self.increasing(Float::signum, 0)
+    }
+
+    fn is_sign_positive(self) -> bool {
+        self.lo.is_sign_positive()
+    }
+
+    fn is_sign_negative(self) -> bool {
+        self.hi.is_sign_negative()
+    }
+
+    fn mul_add(self, a: Self, b: Self) -> Self {
+        self * a + b
+    }
+
+    fn recip(self) -> Self {
+        Self::one() / self
+    }
+
+    fn powi(self, n: i32) -> Self {
+        // the magnitude avoids the overflow of -i32::MIN
+        let power = self.powu(n.unsigned_abs());
+        if n < 0 {
+            power.recip()
+        } else {
+            power
+        }
+    }
+
+    fn powf(self, n: Self) -> Self {
+        (n * self.ln()).exp()
+    }
+
+    fn sqrt(self) -> Self {
+        self.restrict(0.0, f64::INFINITY).increasing(Float::sqrt, 1).clamp(0.0, f64::INFINITY)
+    }
+
+    fn exp(self) -> Self {
+        self.increasing(Float::exp, ULPS).clamp(0.0, f64::INFINITY)
+    }
+
+    fn exp2(self) -> Self {
+        self.increasing(Float::exp2, ULPS).clamp(0.0, f64::INFINITY)
+    }
+
+    fn ln(self) -> Self {
+        self.restrict(0.0, f64::INFINITY).increasing(Float::ln, ULPS)
+    }
+
+    fn log(self, base: Self) -> Self {
+        self.ln() / base.ln()
+    }
+
+    fn log2(self) -> Self {
+        self.restrict(0.0, f64::INFINITY).increasing(Float::log2, ULPS)
+    }
+
+    fn log10(self) -> Self {
+        self.restrict(0.0, f64::INFINITY).increasing(Float::log10, ULPS)
+    }
+
+    fn to_degrees(self) -> Self {
+        self * Self::point(180.0) / Self::PI
+    }
+
+    fn to_radians(self) -> Self {
+        self * Self::PI / Self::point(180.0)
+    }
+
+    fn max(self, other: Self) -> Self {
+        Self { lo: self.lo.max(other.lo), hi: self.hi.max(other.hi) }
+    }
+
+    fn min(self, other: Self) -> Self {
+        Self { lo: self.lo.min(other.lo), hi: self.hi.min(other.hi) }
+    }
+
+    fn abs_sub(self, other: Self) -> Self {
+        Float::max(self - other, Self::zero())
+    }
+
+    fn cbrt(self) -> Self {
+        self.increasing(Float::cbrt, ULPS)
+    }
+
+    fn hypot(self, other: Self) -> Self {
+        (self.powi(2) + other.powi(2)).sqrt()
+    }
+
+    fn sin(self) -> Self {
+        // sin has its maxima at x / π = 1/2 + 2k and its minima at x / π = 3/2 + 2k
+        let t = self / Self::PI - Self::point(0.5);
+        self.periodic(Float::sin, t.may_contain_integer(0), t.may_contain_integer(1))
+    }
+
+    fn cos(self) -> Self {
+        // cos has its maxima at x / π = 2k and its minima at x / π = 2k + 1
+        let t = self / Self::PI;
+        self.periodic(Float::cos, t.may_contain_integer(0), t.may_contain_integer(1))
+    }
+
+    fn tan(self) -> Self {
+        // poles at x / π = 1/2 + k
+        let t = self / Self::PI - Self::point(0.5);
+        if self.is_empty() || t.may_contain_integer(0) || t.may_contain_integer(1) {
+            return Self::ENTIRE;
+        }
+        self.increasing(Float::tan, ULPS)
+    }
+
+    fn asin(self) -> Self {
+        self.restrict(-1.0, 1.0).increasing(Float::asin, ULPS)
+    }
+
+    fn acos(self) -> Self {
+        self.restrict(-1.0, 1.0).decreasing(Float::acos, ULPS).clamp(0.0, f64::INFINITY)
+    }
+
+    fn atan(self) -> Self {
+        self.increasing(Float::atan, ULPS)
+    }
+
+    fn atan2(self, other: Self) -> Self {
+        let half_pi = Self::PI / Self::point(2.0);
+        if self.is_empty() || other.is_empty() {
+            Self::EMPTY
+        } else if other.lo > 0.0 {
+            (self / other).atan()
+        } else if self.lo > 0.0 {
+            half_pi - (other / self).atan()
+        } else if self.hi < 0.0 {
+            -half_pi - (other / self).atan()
+        } else {
+            Self::new(-Self::PI.hi, Self::PI.hi)
+        }
+    }
+
+    fn sin_cos(self) -> (Self, Self) {
+        (self.sin(), self.cos())
+    }
+
+    fn exp_m1(self) -> Self {
+        self.increasing(Float::exp_m1, ULPS).clamp(-1.0, f64::INFINITY)
+    }
+
+    fn ln_1p(self) -> Self {
+        self.restrict(-1.0, f64::INFINITY).increasing(Float::ln_1p, ULPS)
+    }
+
+    fn sinh(self) -> Self {
+        self.increasing(Float::sinh, ULPS)
+    }
+
+    fn cosh(self) -> Self {
+        Float::abs(self).increasing(Float::cosh, ULPS).clamp(1.0, f64::INFINITY)
+    }
+
+    fn tanh(self) -> Self {
+        self.increasing(Float::tanh, ULPS).clamp(-1.0, 1.0)
+    }
+
+    fn asinh(self) -> Self {
+        self.increasing(Float::asinh, ULPS)
+    }
+
+    fn acosh(self) -> Self {
+        self.restrict(1.0, f64::INFINITY).increasing(Float::acosh, ULPS).clamp(0.0, f64::INFINITY)
+    }
+
+    fn atanh(self) -> Self {
+        self.restrict(-1.0, 1.0).increasing(Float::atanh, ULPS)
+    }
+
+    /// Decodes the midpoint
+    fn integer_decode(self) -> (u64, i16, i8) {
+        self.mid().integer_decode()
+    }
+}
+
+impl crate::Piecewise<Interval> for Interval {
+    fn is_less(&self, other: &Self) -> Option<bool> {
+        if self.hi < other.lo {
+            Some(true)
+        } else if self.lo >= other.hi || self.is_empty() || other.is_empty() {
+            Some(false)
+        } else {
+            None
+        }
+    }
+
+    fn is_negative_sign(&self) -> Option<bool> {
+        if self.hi.is_sign_negative() {
+            Some(true)
+        } else if self.lo.is_sign_positive() || self.is_empty() {
+            Some(false)
+        } else {
+            None
+        }
+    }
+
+    fn join(a: crate::Differential<Self, Self>, b: crate::Differential<Self, Self>) -> crate::Differential<Self, Self> {
+        crate::Differential::new(a.value.hull(&b.value), a.derivative.hull(&b.derivative))
+    }
+}
+
+impl Interval {
+    /// Encloses the range of the power `n`
+    fn powu(self, n: u32) -> Self {
+        if n % 2 == 1 && self.lo < 0.0 {
+            // odd powers are increasing
+            let negative = Self::new(0.0, -self.lo).powu(n);
+            let positive = Self::new(0.0, self.hi.max(0.0)).powu(n);
+            return Self::new(-negative.hi, positive.hi);
+        }
+        // exponentiation by squaring of a non negative interval, each product rounds outward
+        let mut base = if n.is_multiple_of(2) { Float::abs(self) } else { self };
+        let mut n = n;
+        let mut result = Self::one();
+        while n > 0 {
+            if n % 2 == 1 {
+                result *= base;
+            }
+            base *= base;
+            n /= 2;
+        }
+        result.clamp(0.0, f64::INFINITY)
+    }
+
+    /// Encloses the range of sin/cos given whether the interval may contain a maximum or a minimum
+    fn periodic(self, f: impl Fn(f64) -> f64, has_max: bool, has_min: bool) -> Self {
+        if self.is_empty() {
+            return Self::EMPTY;
+        }
+        let (a, b) = (f(self.lo), f(self.hi));
+        let lo = if has_min { -1.0 } else { a.min(b) };
+        let hi = if has_max { 1.0 } else { a.max(b) };
+        Self::outward(lo, hi, ULPS).clamp(-1.0, 1.0)
+    }
+}
@@ -0,0 +1,141 @@
+use num_traits::Float;
+
+use super::*;
+
+/// `a * b` with `0 * inf = 0`, as needed for the bounds of interval products
+fn mul_bound(a: f64, b: f64) -> f64 {
+    if a == 0.0 || b == 0.0 {
+        0.0
+    } else {
+        a * b
+    }
+}
+
+impl core::ops::Add for Interval {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Self::outward(self.lo + other.lo, self.hi + other.hi, 1)
+    }
+}
+
+impl core::ops::AddAssign for Interval {
+    fn add_assign(&mut self, other: Self) {
+        *self = *self + other;
+    }
+}
+
+impl core::ops::Neg for Interval {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Self {
+            lo: -self.hi,
+            hi: -self.lo,
+        }
+    }
+}
+
+impl core::ops::Sub for Interval {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        Self::outward(self.lo - other.hi, self.hi - other.lo, 1)
+    }
+}
+
+impl core::ops::SubAssign for Interval {
+    fn sub_assign(&mut self, other: Self) {
+        *self = *self - other;
+    }
+}
+
+impl core::ops::Mul for Interval {
+    type Output = Self;
+
+    fn mul(self, other: Self) -> Self {
+        if self.is_empty() || other.is_empty() {
+            return Self::EMPTY;
+        }
+        let products = [
+            mul_bound(self.lo, other.lo),
+            mul_bound(self.lo, other.hi),
+            mul_bound(self.hi, other.lo),
+            mul_bound(self.hi, other.hi),
+        ];
+        let lo = products.iter().copied().fold(f64::INFINITY, f64::min);
+        let hi = products.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+        Self::outward(lo, hi, 1)
+    }
+}
+
+impl core::ops::MulAssign for Interval {
+    fn mul_assign(&mut self, other: Self) {
+        *self = *self * other;
+    }
+}
+
+impl core::ops::Div for Interval {
+    type Output = Self;
+
+    fn div(self, other: Self) -> Self {
+        if self.is_empty() || other.is_empty() {
+            return Self::EMPTY;
+        }
+        if other.contains(0.0) {
+            return Self::ENTIRE;
+        }
+        let quotients = [
+            self.lo / other.lo,
+            self.lo / other.hi,
+            self.hi / other.lo,
+            self.hi / other.hi,
+        ];
+        let lo = quotients.iter().copied().fold(f64::INFINITY, f64::min);
+        let hi = quotients.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+        Self::outward(lo, hi, 1)
+    }
+}
+
+impl core::ops::DivAssign for Interval {
+    fn div_assign(&mut self, other: Self) {
+        *self = *self / other;
+    }
+}
+
+impl core::ops::Rem for Interval {
+    type Output = Self;
+
+    /// Encloses the truncated remainder `a - b * trunc(a / b)`
+    fn rem(self, other: Self) -> Self {
+        let quotient = self / other;
+        let truncated = Self::outward(Float::trunc(quotient.lo), Float::trunc(quotient.hi), 0);
+        let rem = self - other * truncated;
+        // the remainder is also bounded by the divisor and has the sign of the dividend
+        let bound = other.lo.abs().max(other.hi.abs());
+        let lo = if self.lo >= 0.0 { 0.0 } else { -bound };
+        let hi = if self.hi <= 0.0 { 0.0 } else { bound };
+        rem.intersection(&Self { lo, hi }).unwrap_or(rem)
+    }
+}
+
+impl core::ops::RemAssign for Interval {
+    fn rem_assign(&mut self, other: Self) {
+        *self = *self % other;
+    }
+}
+
+impl PartialOrd for Interval {
+    /// Intervals are ordered only if they are equal or if they do not overlap
+    fn partial_cmp(&self, other: &Self) -> Option<core::cmp::Ordering> {
+        if self == other {
+            Some(core::cmp::Ordering::Equal)
+        } else if self.hi < other.lo {
+            Some(core::cmp::Ordering::Less)
+        } else if self.lo > other.hi {
+            Some(core::cmp::Ordering::Greater)
+        } else {
+            None
+        }
+    }
+}
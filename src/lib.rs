@@ -1,15 +1,42 @@
 /*!
 Provides some differentiation utilities.
+
+The core [`Differential`] type and its operations are `no_std`. The elementary functions
+(`Float`/`Real` impls) need either the `std` (default) or the `libm` feature, while the
+`nalgebra` (default) and `approx` (default) features enable the respective integrations.
 */
 
-use std::fmt::Debug;
+#![cfg_attr(not(feature = "std"), no_std)]
+
+// lets the derive macros refer to `::differential` inside this crate too
+extern crate self as differential;
 
 use num_traits::Zero;
 
 mod impls;
+#[cfg(feature = "nalgebra")]
+pub mod finite_diff;
+#[cfg(feature = "nalgebra")]
+pub mod fit;
+#[cfg(feature = "nalgebra")]
+pub mod implicit;
+#[cfg(any(feature = "std", feature = "libm"))]
+pub mod interval;
+#[cfg(feature = "std")]
+pub mod ode;
+#[cfg(feature = "nalgebra")]
+pub mod optimize;
+#[cfg(any(feature = "std", feature = "libm"))]
+pub mod reduce;
+#[cfg(feature = "nalgebra")]
+pub mod seed;
+#[cfg(feature = "std")]
+pub mod solve;
+pub mod text;
 
 /// A (first order) differential
 #[derive(Debug, Clone, Copy, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Differential<T = f64, D = T>
 {
     /// The value of the function
@@ -19,6 +46,12 @@ pub struct Differential<T = f64, D = T>
     pub derivative: D,
 }
 
+/// A second order differential, obtained by nesting differentials
+///
+/// Seeding the inner and the outer derivatives with two directions yields the corresponding
+/// second directional derivative in `derivative.derivative`.
+pub type Differential2<T = f64> = Differential<Differential<T>, Differential<T>>;
+
 impl<T, D> Differential<T, D> {
     /// Creates a new differential with the given value and derivative
     pub fn new(value: T, derivative: D) -> Self {
@@ -56,6 +89,7 @@ impl<T, D> From<(T, D)> for Differential<T, D>
 
 
 /// Computes the Jacobian matrix of a function f: R^n -> R^m
+#[cfg(feature = "nalgebra")]
 pub fn jacobian(f: impl Fn(&[Differential]) -> Vec<Differential>, params: &[f64]) -> nalgebra::DMatrix<f64> { // TODO move
     let n_params = params.len();
     let n_outputs = {
@@ -73,6 +107,52 @@ pub fn jacobian(f: impl Fn(&[Differential]) -> Vec<Differential>, params: &[f64]
     jacobian
 }
 
+/// A Jacobian matrix together with the point where it was evaluated
+///
+/// With the `serde` feature this can be serialized to checkpoint sensitivity results.
+#[cfg(feature = "nalgebra")]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct JacobianEvaluation {
+    /// The parameters where the Jacobian was evaluated
+    pub params: Vec<f64>,
+
+    /// The outputs of the function at `params`
+    pub outputs: Vec<f64>,
+
+    /// The Jacobian matrix, one row per output and one column per parameter
+    pub jacobian: nalgebra::DMatrix<f64>,
+
+    /// Optional names of the parameters, empty if unnamed
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub param_names: Vec<String>,
+
+    /// Optional names of the outputs, empty if unnamed
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub output_names: Vec<String>,
+}
+
+#[cfg(feature = "nalgebra")]
+impl JacobianEvaluation {
+    /// Evaluates `f` and its Jacobian at `params`
+    pub fn new(f: impl Fn(&[Differential]) -> Vec<Differential>, params: &[f64]) -> Self {
+        let outputs = {
+            let params: Vec<Differential> = params.iter().map(|x| (*x).into()).collect();
+            f(&params).iter().map(|y| y.value).collect()
+        };
+        Self {
+            params: params.to_vec(),
+            outputs,
+            jacobian: jacobian(f, params),
+            param_names: Vec::new(),
+            output_names: Vec::new(),
+        }
+    }
+}
+
+#[cfg(feature = "derive")]
+pub use differential_derive::{IntoDifferentialForm, IntoRealForm};
+
 /// Converts the type into its differential form
 pub trait IntoDifferentialForm {
     /// The output type
@@ -91,6 +171,49 @@ pub trait IntoRealForm {
     fn into_real_form(self) -> Self::RealForm;
 }
 
+/// Converts the type from its differential form, keeping the derivatives as Jacobian blocks
+///
+/// For example, `Vector3<Differential<T, Vector2<T>>>` converts into `(Vector3<T>, Matrix3x2<T>)`.
+pub trait IntoJacobianForm {
+    /// The output type of the values
+    type RealForm;
+
+    /// The output type of the stacked derivatives
+    type JacobianForm;
+
+    /// Converts the type into its values and derivatives
+    fn into_jacobian_form(self) -> (Self::RealForm, Self::JacobianForm);
+}
+
+/// Casts a derivative component-wise, as in [`Differential::cast`]
+///
+/// This is implemented for the primitive numbers, for nested differentials and (with the `nalgebra`
+/// feature) for matrices and vectors.
+pub trait CastTangent<E> {
+    /// Casts every component, returning `None` if one cannot be represented in the target type
+    fn cast_tangent(self) -> Option<E>;
+}
+
+/// Decides the branches of piecewise functions on the values of a [`Differential`]
+///
+/// The [`Float`](num_traits::Float) functions of a differential that depend on a comparison of the
+/// values (`abs`, `copysign`, `max`, `min`, `abs_sub` and `powf`) ask the value type which branch
+/// applies. A scalar that stands for a set of numbers, like an [`Interval`](crate::interval::Interval),
+/// may not be able to decide, and then the results of both branches are joined so that the
+/// derivative is still enclosed.
+pub trait Piecewise<D>: Sized {
+    /// Whether `self < other`, or `None` if that depends on the represented numbers
+    fn is_less(&self, other: &Self) -> Option<bool>;
+
+    /// Whether the sign bit is set, or `None` if that depends on the represented numbers
+    fn is_negative_sign(&self) -> Option<bool>;
+
+    /// Joins the results of two branches into one enclosing both
+    ///
+    /// This is only called after an undecided comparison.
+    fn join(a: Differential<Self, D>, b: Differential<Self, D>) -> Differential<Self, D>;
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -101,4 +224,135 @@ mod tests {
         assert_eq!(Differential::new(1.0, 2.0) - Differential::new(3.0, 4.0), (-2.0, -2.0).into());
         assert_eq!(Differential::new(1.0, 2.0) * Differential::new(3.0, 4.0), (3.0, 10.0).into());
     }
-}
\ No newline at end of file
+
+    #[cfg(feature = "nalgebra")]
+    #[test]
+    fn jacobian_evaluation() {
+        let evaluation = JacobianEvaluation::new(|x| vec![x[0] * x[1], x[0] + x[1]], &[2.0, 3.0]);
+        assert_eq!(evaluation.outputs, vec![6.0, 5.0]);
+        assert_eq!(evaluation.jacobian, nalgebra::DMatrix::from_row_slice(2, 2, &[3.0, 2.0, 1.0, 1.0]));
+    }
+
+    #[cfg(all(feature = "serde", feature = "nalgebra"))]
+    #[test]
+    fn serde_round_trip() {
+        let x = Differential::new(1.5, nalgebra::Vector2::new(1.0, -2.0));
+        let json = serde_json::to_string(&x).unwrap();
+        assert_eq!(json, r#"{"value":1.5,"derivative":[1.0,-2.0]}"#);
+        let y: Differential<f64, nalgebra::Vector2<f64>> = serde_json::from_str(&json).unwrap();
+        assert_eq!(y.derivative, x.derivative);
+
+        let mut evaluation = JacobianEvaluation::new(|x| vec![x[0] * x[0]], &[3.0]);
+        evaluation.param_names = vec!["angle".to_string()];
+        let json = serde_json::to_string(&evaluation).unwrap();
+        assert_eq!(serde_json::from_str::<JacobianEvaluation>(&json).unwrap(), evaluation);
+    }
+
+    #[cfg(feature = "nalgebra")]
+    #[test]
+    fn derive_forms() {
+        use differential_derive::{IntoDifferentialForm, IntoRealForm};
+        use nalgebra::Vector3;
+
+        #[derive(Debug, Clone, PartialEq, IntoDifferentialForm, IntoRealForm)]
+        struct Spring {
+            k: f64,
+        }
+
+        #[derive(Debug, Clone, PartialEq, IntoDifferentialForm, IntoRealForm)]
+        #[differential(name = "DualParams")]
+        struct Params {
+            mass: f64,
+            position: Vector3<f64>,
+            spring: Spring,
+        }
+
+        #[derive(Debug, Clone, PartialEq, IntoDifferentialForm, IntoRealForm)]
+        struct Pair(f32, f64);
+
+        let params = Params { mass: 2.0, position: Vector3::new(1.0, 2.0, 3.0), spring: Spring { k: 0.5 } };
+        let mut dual: DualParams = params.clone().into_differential_form();
+        assert_eq!(dual.mass, Differential::new(2.0, 0.0));
+        assert_eq!(dual.spring.k.derivative, 0.0);
+        dual.mass.derivative = 1.0;
+        assert_eq!(dual.into_real_form(), params);
+
+        let pair: PairDifferential = Pair(1.0, 2.0).into_differential_form();
+        assert_eq!(pair.0.value, 1.0);
+        assert_eq!(pair.into_real_form(), Pair(1.0, 2.0));
+    }
+
+    #[cfg(feature = "nalgebra")]
+    #[test]
+    fn container_and_geometry_forms() {
+        use nalgebra::{DMatrix, Isometry2, Isometry3, Matrix2x3, Point3, Similarity3, UnitQuaternion, Vector3};
+
+        use crate::interval::Interval;
+
+        let pose: Isometry3<f64> = Isometry3::new(Vector3::new(1.0, 2.0, 3.0), Vector3::new(0.1, 0.2, 0.3));
+        let dual = pose.into_differential_form();
+        assert_eq!(dual.translation.vector.x, Differential::new(1.0, 0.0));
+        assert_eq!(dual.rotation.coords.w.value, pose.rotation.w);
+        assert_eq!(dual.into_real_form(), pose);
+
+        let planar: Isometry2<f64> = Isometry2::new(nalgebra::Vector2::new(1.0, -1.0), 0.5);
+        assert_eq!(planar.into_differential_form().into_real_form(), planar);
+
+        let similarity = Similarity3::from_parts(pose.translation, UnitQuaternion::identity(), 2.0);
+        let (isometry, scaling) = similarity.into_differential_form().into_real_form();
+        assert_eq!(Similarity3::from_isometry(isometry, scaling), similarity);
+
+        let matrix = Matrix2x3::new(1.0, 2.0, 3.0, 4.0, 5.0, 6.0);
+        assert_eq!(matrix.into_differential_form()[(1, 2)], Differential::new(6.0, 0.0));
+        let dynamic = DMatrix::from_element(2, 2, 1.5f32);
+        assert_eq!(dynamic.clone().into_differential_form().into_real_form(), dynamic);
+        let point = Point3::new(1.0, 2.0, 3.0);
+        assert_eq!(point.into_differential_form().into_real_form(), point);
+
+        let first = nalgebra::Vector2::new(Differential::new(1.0, 2.0), Differential::new(3.0, 4.0));
+        let second = first.into_differential_form();
+        assert_eq!(second.y, Differential::new(Differential::new(3.0, 4.0), Differential::new(0.0, 0.0)));
+        assert_eq!(second.into_real_form(), first);
+        let bounds = Vector3::new(Interval::new(0.0, 1.0), Interval::point(2.0), Interval::new(-1.0, 1.0));
+        assert_eq!(bounds.into_differential_form().z, Differential::new(Interval::new(-1.0, 1.0), Interval::point(0.0)));
+
+        let nested = (vec![1.0, 2.0], [Some(3.0f32), None], Vector3::new(4.0, 5.0, 6.0));
+        let dual = nested.clone().into_differential_form();
+        assert_eq!(dual.1[0], Some(Differential::new(3.0, 0.0)));
+        assert_eq!(dual.into_real_form(), nested);
+    }
+
+    #[cfg(feature = "nalgebra")]
+    #[test]
+    fn jacobian_forms() {
+        use nalgebra::{DMatrix, DVector, Matrix3x2, Vector2, Vector3};
+
+        let x = Differential::new(1.0, Vector2::new(1.0, 0.0));
+        let y = Differential::new(2.0, Vector2::new(0.0, 1.0));
+        let (value, jacobian) = Vector3::new(x * y, x + y, y * y).into_jacobian_form();
+        assert_eq!(value, Vector3::new(2.0, 3.0, 4.0));
+        assert_eq!(jacobian, Matrix3x2::new(2.0, 1.0, 1.0, 1.0, 0.0, 4.0));
+
+        let outputs = DVector::from_vec(vec![Differential::new(1.0, DVector::from_vec(vec![1.0, 2.0, 3.0]))]);
+        let ((value, scalar), (block, tangent)) = (outputs, x * x).into_jacobian_form();
+        assert_eq!(value, DVector::from_vec(vec![1.0]));
+        assert_eq!(block, DMatrix::from_row_slice(1, 3, &[1.0, 2.0, 3.0]));
+        assert_eq!((scalar, tangent), (1.0, Vector2::new(2.0, 0.0)));
+
+        let empty: DVector<Differential<f64, DVector<f64>>> = DVector::from_vec(Vec::new());
+        assert_eq!(empty.into_jacobian_form().1.shape(), (0, 0));
+    }
+
+    #[cfg(feature = "nalgebra")]
+    #[test]
+    #[should_panic(expected = "the derivative of component 1 has a different length than the first one")]
+    fn jacobian_form_with_mismatched_derivatives() {
+        use nalgebra::DVector;
+
+        let outputs = DVector::from_vec(vec![
+            Differential::new(1.0, DVector::from_vec(vec![1.0, 2.0])),
+            Differential::new(2.0, DVector::from_vec(vec![1.0])),
+        ]);
+        let _ = outputs.into_jacobian_form();
+    }
+}
@@ -0,0 +1,435 @@
+/*!
+Explicit Runge–Kutta integrators for `dy/dt = f(t, y)`.
+
+The integrators are generic over [`Real`], so the state can be made of [`Differential`]s: seeding
+the initial conditions or the parameters captured by `f` then yields forward sensitivities of the
+solution without any additional code.
+
+The adaptive step-size control of [`dormand_prince`] only looks at the values of the state, so the
+steps, and thus the values of the solution, are the same with and without tangents.
+
+With the `nalgebra` feature, [`forward_sensitivities`] instead integrates the sensitivity equations
+alongside the state, with the Jacobians of `f` computed by [`jacobian`](crate::jacobian).
+
+[`Differential`]: crate::Differential
+*/
+
+use num_traits::real::Real;
+
+#[cfg(feature = "nalgebra")]
+mod sensitivity;
+#[cfg(feature = "nalgebra")]
+pub use sensitivity::{forward_sensitivities, SensitivitySolution};
+
+/// Options of [`dormand_prince`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DormandPrinceOptions {
+    /// The relative tolerance of the local error
+    pub relative_tolerance: f64,
+
+    /// The absolute tolerance of the local error
+    pub absolute_tolerance: f64,
+
+    /// The first step, estimated from `f` if `None`
+    pub initial_step: Option<f64>,
+
+    /// The longest step
+    pub max_step: f64,
+
+    /// Stop when the step has to be shortened below this
+    pub min_step: f64,
+
+    /// Maximum number of steps, accepted or rejected
+    pub max_steps: usize,
+}
+
+impl Default for DormandPrinceOptions {
+    fn default() -> Self {
+        Self {
+            relative_tolerance: 1e-6,
+            absolute_tolerance: 1e-9,
+            initial_step: None,
+            max_step: f64::INFINITY,
+            min_step: 1e-12,
+            max_steps: 100_000,
+        }
+    }
+}
+
+/// Why an integration stopped
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OdeStopReason {
+    /// The solution reached the last output time
+    Completed,
+
+    /// The maximum number of steps was reached
+    MaxSteps,
+
+    /// The step had to be shortened below the minimum
+    MinStep,
+
+    /// The derivatives are not finite
+    NonFinite,
+}
+
+/// The solution of an initial value problem at the output times
+#[derive(Debug, Clone, PartialEq)]
+pub struct OdeSolution<T> {
+    /// The output times that were reached
+    pub times: Vec<f64>,
+
+    /// The states at `times`
+    pub states: Vec<Vec<T>>,
+
+    /// The number of accepted steps
+    pub steps: usize,
+
+    /// The number of rejected steps
+    pub rejected_steps: usize,
+
+    /// The number of evaluations of `f`
+    pub evaluations: usize,
+
+    /// Why the integration stopped
+    pub reason: OdeStopReason,
+}
+
+impl<T> OdeSolution<T> {
+    /// Returns `true` if the solution reached the last output time
+    pub fn completed(&self) -> bool {
+        self.reason == OdeStopReason::Completed
+    }
+}
+
+fn constant<T: Real>(c: f64) -> T {
+    T::from(c).unwrap()
+}
+
+/// Returns `y + h Σ cⱼ kⱼ`
+fn combine<T: Real>(y: &[T], h: f64, terms: &[(f64, &[T])]) -> Vec<T> {
+    y.iter()
+        .enumerate()
+        .map(|(i, &y)| y + terms.iter().fold(T::zero(), |acc, &(c, k)| acc + constant::<T>(h * c) * k[i]))
+        .collect()
+}
+
+fn is_finite<T: Real>(y: &[T]) -> bool {
+    y.iter().all(|y| y.to_f64().is_some_and(f64::is_finite))
+}
+
+/// Integrates `dy/dt = f(t, y)` from `times[0]` through the other `times` with the classical
+/// fourth-order Runge–Kutta method
+///
+/// Each interval between output times is split into equal steps of at most `max_step`. The times
+/// may also decrease, to integrate backwards.
+///
+/// # Panics
+///
+/// If `max_step` is not positive and finite.
+pub fn rk4<T: Real>(f: impl Fn(f64, &[T]) -> Vec<T>, y0: &[T], times: &[f64], max_step: f64) -> OdeSolution<T> {
+    assert!(max_step.is_finite() && max_step > 0.0, "the maximum step must be positive and finite");
+    let mut y = y0.to_vec();
+    let mut solution = OdeSolution {
+        times: times.first().copied().into_iter().collect(),
+        states: times.first().map(|_| y.clone()).into_iter().collect(),
+        steps: 0,
+        rejected_steps: 0,
+        evaluations: 0,
+        reason: OdeStopReason::Completed,
+    };
+    for interval in times.windows(2) {
+        let (start, end) = (interval[0], interval[1]);
+        let count = ((end - start).abs() / max_step).ceil().max(1.0) as usize;
+        let h = (end - start) / count as f64;
+        for step in 0..count {
+            let t = start + step as f64 * h;
+            let k1 = f(t, &y);
+            let k2 = f(t + 0.5 * h, &combine(&y, h, &[(0.5, &k1)]));
+            let k3 = f(t + 0.5 * h, &combine(&y, h, &[(0.5, &k2)]));
+            let k4 = f(t + h, &combine(&y, h, &[(1.0, &k3)]));
+            solution.evaluations += 4;
+            if !(is_finite(&k1) && is_finite(&k2) && is_finite(&k3) && is_finite(&k4)) {
+                solution.reason = OdeStopReason::NonFinite;
+                return solution;
+            }
+            y = combine(&y, h, &[(1.0 / 6.0, &k1), (1.0 / 3.0, &k2), (1.0 / 3.0, &k3), (1.0 / 6.0, &k4)]);
+            solution.steps += 1;
+        }
+        solution.times.push(end);
+        solution.states.push(y.clone());
+    }
+    solution
+}
+
+// the Butcher tableau of Dormand–Prince 5(4)
+const C: [f64; 6] = [1.0 / 5.0, 3.0 / 10.0, 4.0 / 5.0, 8.0 / 9.0, 1.0, 1.0];
+const A2: [f64; 1] = [1.0 / 5.0];
+const A3: [f64; 2] = [3.0 / 40.0, 9.0 / 40.0];
+const A4: [f64; 3] = [44.0 / 45.0, -56.0 / 15.0, 32.0 / 9.0];
+const A5: [f64; 4] = [19372.0 / 6561.0, -25360.0 / 2187.0, 64448.0 / 6561.0, -212.0 / 729.0];
+const A6: [f64; 5] = [9017.0 / 3168.0, -355.0 / 33.0, 46732.0 / 5247.0, 49.0 / 176.0, -5103.0 / 18656.0];
+const B: [f64; 6] = [35.0 / 384.0, 0.0, 500.0 / 1113.0, 125.0 / 192.0, -2187.0 / 6784.0, 11.0 / 84.0];
+// the differences between the fifth- and fourth-order weights, including the FSAL stage
+const E: [f64; 7] = [
+    71.0 / 57600.0,
+    0.0,
+    -71.0 / 16695.0,
+    71.0 / 1920.0,
+    -17253.0 / 339200.0,
+    22.0 / 525.0,
+    -1.0 / 40.0,
+];
+
+/// The weighted RMS norm of the values of `e`, scaled by the tolerances
+fn error_norm<T: Real>(e: &[f64], y: &[T], next: &[T], options: &DormandPrinceOptions) -> f64 {
+    if e.is_empty() {
+        return 0.0;
+    }
+    let sum: f64 = e
+        .iter()
+        .zip(y.iter().zip(next))
+        .map(|(e, (y, next))| {
+            let magnitude = y.to_f64().unwrap().abs().max(next.to_f64().unwrap().abs());
+            (e / (options.absolute_tolerance + options.relative_tolerance * magnitude)).powi(2)
+        })
+        .sum();
+    (sum / e.len() as f64).sqrt()
+}
+
+/// The RMS norm of the values of `y`, scaled by the tolerances at `y0`
+fn scaled_norm<T: Real>(y: &[T], y0: &[T], options: &DormandPrinceOptions) -> f64 {
+    let e: Vec<f64> = y.iter().map(|y| y.to_f64().unwrap()).collect();
+    error_norm(&e, y0, y0, options)
+}
+
+/// Hairer's estimate of the first step from the values of `y0` and `f(t0, y0)`
+fn initial_step<T: Real>(
+    f: &impl Fn(f64, &[T]) -> Vec<T>,
+    t0: f64,
+    y0: &[T],
+    f0: &[T],
+    direction: f64,
+    options: &DormandPrinceOptions,
+) -> f64 {
+    let (d0, d1) = (scaled_norm(y0, y0, options), scaled_norm(f0, y0, options));
+    let h0 = if d0 < 1e-5 || d1 < 1e-5 { 1e-6 } else { 0.01 * d0 / d1 };
+    let f1 = f(t0 + direction * h0, &combine(y0, direction * h0, &[(1.0, f0)]));
+    let difference: Vec<T> = f1.iter().zip(f0).map(|(&a, &b)| a - b).collect();
+    let d2 = scaled_norm(&difference, y0, options) / h0;
+    let h1 = if d1.max(d2) <= 1e-15 {
+        (h0 * 1e-3).max(1e-6)
+    } else {
+        (0.01 / d1.max(d2)).powf(0.2)
+    };
+    (100.0 * h0).min(h1)
+}
+
+/// Integrates `dy/dt = f(t, y)` from `times[0]` through the other `times` with the adaptive
+/// Dormand–Prince 5(4) method
+///
+/// The local error of the embedded fourth-order solution is kept below the tolerances, and the
+/// steps are shortened to land exactly on the output times. The times may also decrease, to
+/// integrate backwards.
+///
+/// # Panics
+///
+/// If a tolerance is negative or both are zero.
+pub fn dormand_prince<T: Real>(
+    f: impl Fn(f64, &[T]) -> Vec<T>,
+    y0: &[T],
+    times: &[f64],
+    options: &DormandPrinceOptions,
+) -> OdeSolution<T> {
+    let (relative, absolute) = (options.relative_tolerance, options.absolute_tolerance);
+    assert!(relative >= 0.0 && absolute >= 0.0 && relative + absolute > 0.0, "the tolerances must be non-negative and not both zero");
+    let mut y = y0.to_vec();
+    let mut solution = OdeSolution {
+        times: times.first().copied().into_iter().collect(),
+        states: times.first().map(|_| y.clone()).into_iter().collect(),
+        steps: 0,
+        rejected_steps: 0,
+        evaluations: 0,
+        reason: OdeStopReason::Completed,
+    };
+    let (Some(&first), Some(&last)) = (times.first(), times.last()) else {
+        return solution;
+    };
+    let direction = if last < first { -1.0 } else { 1.0 };
+
+    let mut t = first;
+    let mut k1 = f(t, &y);
+    solution.evaluations += 1;
+    if !is_finite(&k1) {
+        solution.reason = OdeStopReason::NonFinite;
+        return solution;
+    }
+    let mut h = match options.initial_step {
+        Some(h) => h.abs(),
+        None => {
+            solution.evaluations += 1;
+            initial_step(&f, t, &y, &k1, direction, options)
+        }
+    }
+    .min(options.max_step);
+
+    for &target in &times[1..] {
+        while (target - t) * direction > 0.0 {
+            if solution.steps + solution.rejected_steps >= options.max_steps {
+                solution.reason = OdeStopReason::MaxSteps;
+                return solution;
+            }
+            if h < options.min_step {
+                solution.reason = OdeStopReason::MinStep;
+                return solution;
+            }
+            // land exactly on the output time, and avoid a tiny step right before it
+            let remaining = (target - t).abs();
+            let lands = 1.01 * h >= remaining;
+            let step = direction * if lands { remaining } else { h };
+
+            let k2 = f(t + C[0] * step, &combine(&y, step, &[(A2[0], &k1)]));
+            let k3 = f(t + C[1] * step, &combine(&y, step, &[(A3[0], &k1), (A3[1], &k2)]));
+            let k4 = f(t + C[2] * step, &combine(&y, step, &[(A4[0], &k1), (A4[1], &k2), (A4[2], &k3)]));
+            let k5 = f(
+                t + C[3] * step,
+                &combine(&y, step, &[(A5[0], &k1), (A5[1], &k2), (A5[2], &k3), (A5[3], &k4)]),
+            );
+            let k6 = f(
+                t + C[4] * step,
+                &combine(&y, step, &[(A6[0], &k1), (A6[1], &k2), (A6[2], &k3), (A6[3], &k4), (A6[4], &k5)]),
+            );
+            let next = combine(&y, step, &[(B[0], &k1), (B[2], &k3), (B[3], &k4), (B[4], &k5), (B[5], &k6)]);
+            let k7 = f(t + C[5] * step, &next);
+            solution.evaluations += 6;
+
+            // the error estimate only uses the values, so that the tangents do not affect the steps
+            let stages = [&k1, &k2, &k3, &k4, &k5, &k6, &k7];
+            let error: Vec<f64> = (0..y.len())
+                .map(|i| step * stages.iter().zip(E).map(|(k, e)| e * k[i].to_f64().unwrap_or(f64::NAN)).sum::<f64>())
+                .collect();
+            let norm = error_norm(&error, &y, &next, options);
+            let finite = norm.is_finite() && is_finite(&next) && is_finite(&k7);
+
+            if finite && norm <= 1.0 {
+                t = if lands { target } else { t + step };
+                y = next;
+                k1 = k7;
+                solution.steps += 1;
+            } else {
+                solution.rejected_steps += 1;
+            }
+            let factor = if !finite {
+                0.2
+            } else if norm == 0.0 {
+                5.0
+            } else {
+                (0.9 * norm.powf(-0.2)).clamp(0.2, 5.0)
+            };
+            // do not grow the step right after a rejection
+            let mut next_step = step.abs() * if norm > 1.0 { factor.min(1.0) } else { factor };
+            if lands && norm <= 1.0 {
+                // a step shortened to land on an output time says little about the next one
+                next_step = next_step.max(h);
+            }
+            h = next_step.min(options.max_step);
+        }
+        solution.times.push(target);
+        solution.states.push(y.clone());
+    }
+    solution
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Differential;
+
+    /// `y' = -k y`, with `y(t) = y0 e^(-kt)` and `dy/dk = -t y(t)`
+    fn decay<T: Real>(k: T) -> impl Fn(f64, &[T]) -> Vec<T> {
+        move |_, y| vec![-k * y[0]]
+    }
+
+    #[test]
+    fn rk4_parameter_sensitivity() {
+        let k = Differential::new(0.7, 1.0);
+        let times = [0.0, 1.0, 2.0];
+        let solution = rk4(decay(k), &[Differential::from(2.0)], &times, 0.01);
+        assert!(solution.completed());
+        assert_eq!(solution.steps, 200);
+        for (&t, y) in solution.times.iter().zip(&solution.states) {
+            let exact = 2.0 * (-0.7 * t).exp();
+            assert!((y[0].value - exact).abs() < 1e-10);
+            assert!((y[0].derivative + t * exact).abs() < 1e-10);
+        }
+    }
+
+    #[test]
+    fn dormand_prince_initial_condition_sensitivities() {
+        // the harmonic oscillator x'' = -x, whose flow is a rotation
+        let oscillator = |_: f64, y: &[Differential]| vec![y[1], -y[0]];
+        let options = DormandPrinceOptions {
+            relative_tolerance: 1e-10,
+            absolute_tolerance: 1e-12,
+            ..Default::default()
+        };
+        let times = [0.0, 1.0, 2.5, 5.0];
+        for (j, seed) in [(1.0, 0.0), (0.0, 1.0)].into_iter().enumerate() {
+            let y0 = [Differential::new(1.0, seed.0), Differential::new(0.5, seed.1)];
+            let solution = dormand_prince(oscillator, &y0, &times, &options);
+            assert!(solution.completed());
+            assert_eq!(solution.times, times);
+            for (&t, y) in solution.times.iter().zip(&solution.states) {
+                assert!((y[0].value - (t.cos() + 0.5 * t.sin())).abs() < 1e-8);
+                let expected = [[t.cos(), t.sin()], [-t.sin(), t.cos()]];
+                assert!((y[0].derivative - expected[0][j]).abs() < 1e-8);
+                assert!((y[1].derivative - expected[1][j]).abs() < 1e-8);
+            }
+        }
+    }
+
+    #[test]
+    fn steps_do_not_depend_on_tangents() {
+        let times = [0.0, 3.0];
+        let plain = dormand_prince(decay(0.7), &[2.0], &times, &Default::default());
+        let seeded = dormand_prince(decay(Differential::new(0.7, 1e6)), &[Differential::new(2.0, 1e6)], &times, &Default::default());
+        assert_eq!(plain.steps, seeded.steps);
+        assert_eq!(plain.rejected_steps, seeded.rejected_steps);
+        assert_eq!(plain.states[1][0], seeded.states[1][0].value);
+        let exact = 2.0 * (-0.7 * 3.0f64).exp();
+        assert!((seeded.states[1][0].derivative - 1e6 * (exact / 2.0 - 3.0 * exact)).abs() < 1e-5 * 1e6 * exact);
+    }
+
+    #[test]
+    fn backward_integration_and_failures() {
+        let solution = dormand_prince(decay(1.0), &[1.0], &[1.0, 0.0], &Default::default());
+        assert!(solution.completed());
+        assert!((solution.states[1][0] - 1f64.exp()).abs() < 1e-6);
+
+        // y' = y² blows up at t = 1
+        let blow_up = |_: f64, y: &[f64]| vec![y[0] * y[0]];
+        let solution = dormand_prince(blow_up, &[1.0], &[0.0, 2.0], &Default::default());
+        assert!(!solution.completed());
+        assert_eq!(solution.times, [0.0]);
+        let limited = DormandPrinceOptions { max_steps: 3, ..Default::default() };
+        assert_eq!(dormand_prince(decay(1.0), &[1.0], &[0.0, 100.0], &limited).reason, OdeStopReason::MaxSteps);
+    }
+
+    #[test]
+    #[should_panic(expected = "the maximum step must be positive and finite")]
+    fn rk4_rejects_zero_max_step() {
+        rk4(decay(1.0), &[1.0], &[0.0, 1.0], 0.0);
+    }
+
+    #[test]
+    #[should_panic(expected = "the maximum step must be positive and finite")]
+    fn rk4_rejects_nan_max_step() {
+        rk4(decay(1.0), &[1.0], &[0.0, 1.0], f64::NAN);
+    }
+
+    #[test]
+    #[should_panic(expected = "the tolerances must be non-negative and not both zero")]
+    fn dormand_prince_rejects_zero_tolerances() {
+        let options = DormandPrinceOptions { relative_tolerance: 0.0, absolute_tolerance: 0.0, ..Default::default() };
+        dormand_prince(decay(1.0), &[1.0], &[0.0, 1.0], &options);
+    }
+}
@@ -0,0 +1,139 @@
+use nalgebra::{DMatrix, DVector};
+
+use super::{dormand_prince, DormandPrinceOptions, OdeStopReason};
+use crate::{Differential, JacobianEvaluation};
+
+/// The solution of an initial value problem and its sensitivities to the parameters
+#[derive(Debug, Clone, PartialEq)]
+pub struct SensitivitySolution {
+    /// The output times that were reached
+    pub times: Vec<f64>,
+
+    /// The states at `times`
+    pub states: Vec<DVector<f64>>,
+
+    /// The sensitivities `∂y/∂p` at `times`, one row per state component and one column per
+    /// parameter
+    pub sensitivities: Vec<DMatrix<f64>>,
+
+    /// The number of accepted steps
+    pub steps: usize,
+
+    /// The number of rejected steps
+    pub rejected_steps: usize,
+
+    /// The number of evaluations of `f` and its Jacobians
+    pub evaluations: usize,
+
+    /// Why the integration stopped
+    pub reason: OdeStopReason,
+}
+
+impl SensitivitySolution {
+    /// Returns `true` if the solution reached the last output time
+    pub fn completed(&self) -> bool {
+        self.reason == OdeStopReason::Completed
+    }
+}
+
+/// Integrates `dy/dt = f(t, y, p)` together with its forward sensitivity equations
+///
+/// The sensitivities `S = ∂y/∂p` follow `dS/dt = (∂f/∂y) S + ∂f/∂p` from `S(t₀) = 0`, with the
+/// Jacobians computed by [`jacobian`](crate::jacobian) at every stage. The state and the
+/// sensitivities are integrated as one system with [`dormand_prince`], so the local error control
+/// covers the sensitivities too.
+///
+/// Compared to propagating [`Differential`]s through [`dormand_prince`], which takes one
+/// integration per parameter with scalar tangents, all the sensitivities come from a single
+/// integration.
+pub fn forward_sensitivities(
+    f: impl Fn(f64, &[Differential], &[Differential]) -> Vec<Differential>,
+    y0: &[f64],
+    p: &[f64],
+    times: &[f64],
+    options: &DormandPrinceOptions,
+) -> SensitivitySolution {
+    let (n, m) = (y0.len(), p.len());
+    let augmented = |t: f64, z: &[f64]| {
+        let point: Vec<f64> = z[..n].iter().chain(p).copied().collect();
+        let evaluation = JacobianEvaluation::new(|w| f(t, &w[..n], &w[n..]), &point);
+        let sensitivities = DMatrix::from_column_slice(n, m, &z[n..]);
+        let dy = evaluation.jacobian.columns(0, n) * sensitivities + evaluation.jacobian.columns(n, m);
+        evaluation.outputs.into_iter().chain(dy.iter().copied()).collect::<Vec<f64>>()
+    };
+    let z0: Vec<f64> = y0.iter().copied().chain(core::iter::repeat_n(0.0, n * m)).collect();
+    let solution = dormand_prince(augmented, &z0, times, options);
+
+    SensitivitySolution {
+        states: solution.states.iter().map(|z| DVector::from_column_slice(&z[..n])).collect(),
+        sensitivities: solution.states.iter().map(|z| DMatrix::from_column_slice(n, m, &z[n..])).collect(),
+        times: solution.times,
+        steps: solution.steps,
+        rejected_steps: solution.rejected_steps,
+        evaluations: solution.evaluations,
+        reason: solution.reason,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The consecutive reactions A → B → C with the rates `k₁` and `k₂`
+    fn kinetics(_: f64, y: &[Differential], k: &[Differential]) -> Vec<Differential> {
+        vec![-k[0] * y[0], k[0] * y[0] - k[1] * y[1]]
+    }
+
+    #[test]
+    fn decay() {
+        let solution = forward_sensitivities(|_, y, k| vec![-k[0] * y[0]], &[2.0], &[0.7], &[0.0, 1.0, 3.0], &Default::default());
+        assert!(solution.completed());
+        assert_eq!(solution.sensitivities[0], DMatrix::zeros(1, 1));
+        for ((&t, y), s) in solution.times.iter().zip(&solution.states).zip(&solution.sensitivities) {
+            let exact = 2.0 * (-0.7 * t).exp();
+            assert!((y[0] - exact).abs() < 1e-6 * exact);
+            assert!((s[(0, 0)] + t * exact).abs() < 1e-6 * exact.max(t * exact));
+        }
+    }
+
+    #[test]
+    fn consecutive_reactions() {
+        let (k1, k2) = (1.3, 0.4);
+        let options = DormandPrinceOptions {
+            relative_tolerance: 1e-10,
+            absolute_tolerance: 1e-12,
+            ..Default::default()
+        };
+        let times = [0.0, 0.5, 2.0, 6.0];
+        let solution = forward_sensitivities(kinetics, &[1.0, 0.0], &[k1, k2], &times, &options);
+        assert!(solution.completed());
+        assert_eq!(solution.times, times);
+
+        // b(t) = k₁ (e^(-k₁t) - e^(-k₂t)) / (k₂ - k₁)
+        let b = |k1: f64, k2: f64, t: f64| k1 * ((-k1 * t).exp() - (-k2 * t).exp()) / (k2 - k1);
+        let h = 1e-6;
+        for ((&t, y), s) in times.iter().zip(&solution.states).zip(&solution.sensitivities) {
+            assert!((y[0] - (-k1 * t).exp()).abs() < 1e-9);
+            assert!((y[1] - b(k1, k2, t)).abs() < 1e-9);
+            assert!((s[(0, 0)] + t * (-k1 * t).exp()).abs() < 1e-9);
+            assert_eq!(s[(0, 1)], 0.0);
+            assert!((s[(1, 0)] - (b(k1 + h, k2, t) - b(k1 - h, k2, t)) / (2.0 * h)).abs() < 1e-7);
+            assert!((s[(1, 1)] - (b(k1, k2 + h, t) - b(k1, k2 - h, t)) / (2.0 * h)).abs() < 1e-7);
+        }
+    }
+
+    #[test]
+    fn agrees_with_propagated_differentials() {
+        let options = DormandPrinceOptions {
+            relative_tolerance: 1e-10,
+            absolute_tolerance: 1e-12,
+            ..Default::default()
+        };
+        let times = [0.0, 4.0];
+        let solution = forward_sensitivities(kinetics, &[1.0, 0.0], &[1.3, 0.4], &times, &options);
+        let k = [Differential::from(1.3), Differential::new(0.4, 1.0)];
+        let y0 = [Differential::from(1.0), Differential::from(0.0)];
+        let propagated = dormand_prince(|t, y: &[Differential]| kinetics(t, y, &k), &y0, &times, &options);
+        assert!((solution.sensitivities[1][(1, 1)] - propagated.states[1][1].derivative).abs() < 1e-8);
+    }
+}
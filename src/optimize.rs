@@ -0,0 +1,457 @@
+/*!
+Unconstrained minimization of objectives written against [`Differential`].
+
+The gradients are computed exactly by forward differentiation, so only the objective has to be
+written. The line searches seed the differentials with the search direction, which yields the
+directional derivative in a single evaluation.
+
+[`minimize`] uses quasi-Newton approximations of the curvature, while [`trust_region`] uses exact
+second derivatives from nested differentials ([`Differential2`](crate::Differential2)).
+[`augmented_lagrangian`] solves problems with equality and inequality constraints.
+*/
+
+use std::collections::VecDeque;
+
+use nalgebra::{DMatrix, DVector};
+
+use crate::Differential;
+
+mod constrained;
+mod trust_region;
+pub use constrained::{
+    augmented_lagrangian, AugmentedLagrangianOptions, ConstrainedProblem, ConstrainedReport, ConstrainedStopReason, KktResiduals,
+};
+pub use trust_region::{
+    hessian, hessian_vector_product, trust_region, Subproblem, TrustRegionOptions, TrustRegionReport, TrustRegionStopReason,
+    TrustRegionTrace,
+};
+
+/// Evaluates `f` and its gradient at `x`
+///
+/// The gradient takes one evaluation of `f` per parameter.
+pub fn gradient(f: impl Fn(&[Differential]) -> Differential, x: &[f64]) -> (f64, DVector<f64>) {
+    // the value comes with the partial derivatives, except without parameters
+    let mut value = if x.is_empty() { f(&[]).value } else { 0.0 };
+    let gradient = DVector::from_fn(x.len(), |i, _| {
+        let params: Vec<Differential> = x.iter().enumerate().map(|(j, &x)| Differential::new(x, if i == j { 1.0 } else { 0.0 })).collect();
+        let y = f(&params);
+        value = y.value;
+        y.derivative
+    });
+    (value, gradient)
+}
+
+/// The quasi-Newton method of [`minimize`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QuasiNewton {
+    /// BFGS with a dense inverse-Hessian approximation
+    Bfgs,
+
+    /// Limited-memory BFGS keeping the given number of correction pairs
+    Lbfgs {
+        /// The number of correction pairs
+        memory: usize,
+    },
+}
+
+impl Default for QuasiNewton {
+    fn default() -> Self {
+        Self::Lbfgs { memory: 10 }
+    }
+}
+
+/// Options of [`minimize`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MinimizeOptions {
+    /// The quasi-Newton method
+    pub method: QuasiNewton,
+
+    /// Stop when the largest component of the gradient is at most this
+    pub gradient_tolerance: f64,
+
+    /// Maximum number of iterations
+    pub max_iterations: usize,
+
+    /// The sufficient decrease parameter `c₁` of the Wolfe conditions
+    pub c1: f64,
+
+    /// The curvature parameter `c₂` of the strong Wolfe conditions
+    pub c2: f64,
+
+    /// Maximum number of evaluations of a single line search
+    pub max_line_search_evaluations: usize,
+
+    /// Return the final inverse-Hessian approximation in the report
+    pub inverse_hessian: bool,
+}
+
+impl Default for MinimizeOptions {
+    fn default() -> Self {
+        Self {
+            method: QuasiNewton::default(),
+            gradient_tolerance: 1e-8,
+            max_iterations: 1000,
+            c1: 1e-4,
+            c2: 0.9,
+            max_line_search_evaluations: 50,
+            inverse_hessian: false,
+        }
+    }
+}
+
+/// Why [`minimize`] stopped
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MinimizeStopReason {
+    /// The gradient reached its tolerance
+    GradientTolerance,
+
+    /// The line search found no point satisfying the strong Wolfe conditions
+    LineSearchFailed,
+
+    /// The maximum number of iterations was reached
+    MaxIterations,
+
+    /// The objective or its gradient is not finite
+    NonFinite,
+}
+
+/// The state after an iteration of [`minimize`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct IterationTrace {
+    /// The objective value
+    pub value: f64,
+
+    /// The largest absolute component of the gradient
+    pub gradient_norm: f64,
+
+    /// The step length accepted by the line search
+    pub step_length: f64,
+
+    /// The number of evaluations spent by the line search
+    pub line_search_evaluations: usize,
+}
+
+/// The result of [`minimize`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct MinimizeReport {
+    /// The last iterate
+    pub x: Vec<f64>,
+
+    /// The objective value at `x`
+    pub value: f64,
+
+    /// The gradient at `x`
+    pub gradient: Vec<f64>,
+
+    /// The number of iterations
+    pub iterations: usize,
+
+    /// The number of evaluations of the objective, including those for the gradients
+    pub evaluations: usize,
+
+    /// The state after every iteration
+    pub trace: Vec<IterationTrace>,
+
+    /// The final inverse-Hessian approximation, if requested
+    pub inverse_hessian: Option<DMatrix<f64>>,
+
+    /// Why the iteration stopped
+    pub reason: MinimizeStopReason,
+}
+
+impl MinimizeReport {
+    /// Returns `true` if the gradient reached its tolerance
+    pub fn converged(&self) -> bool {
+        self.reason == MinimizeStopReason::GradientTolerance
+    }
+}
+
+/// The inverse-Hessian approximation
+enum InverseHessian {
+    Dense(Option<DMatrix<f64>>),
+    Limited { memory: usize, pairs: VecDeque<(DVector<f64>, DVector<f64>, f64)> },
+}
+
+impl InverseHessian {
+    fn new(method: QuasiNewton) -> Self {
+        match method {
+            QuasiNewton::Bfgs => Self::Dense(None),
+            QuasiNewton::Lbfgs { memory } => Self::Limited { memory: memory.max(1), pairs: VecDeque::new() },
+        }
+    }
+
+    /// `H v`, where `H` starts as the identity
+    fn apply(&self, v: &DVector<f64>) -> DVector<f64> {
+        match self {
+            Self::Dense(None) => v.clone(),
+            Self::Dense(Some(h)) => h * v,
+            Self::Limited { pairs, .. } => {
+                // two-loop recursion
+                let mut q = v.clone();
+                let mut alphas = Vec::with_capacity(pairs.len());
+                for (s, y, rho) in pairs.iter().rev() {
+                    let alpha = rho * s.dot(&q);
+                    q.axpy(-alpha, y, 1.0);
+                    alphas.push(alpha);
+                }
+                if let Some((s, y, _)) = pairs.back() {
+                    q *= s.dot(y) / y.norm_squared();
+                }
+                for ((s, y, rho), alpha) in pairs.iter().zip(alphas.into_iter().rev()) {
+                    let beta = rho * y.dot(&q);
+                    q.axpy(alpha - beta, s, 1.0);
+                }
+                q
+            }
+        }
+    }
+
+    /// Updates with the step `s` and the gradient change `y`, skipping pairs without positive curvature
+    fn update(&mut self, s: DVector<f64>, y: DVector<f64>) {
+        let sy = s.dot(&y);
+        if sy.is_nan() || sy <= f64::EPSILON * s.norm() * y.norm() {
+            return;
+        }
+        let rho = 1.0 / sy;
+        match self {
+            Self::Dense(h) => {
+                let n = s.len();
+                // the first update rescales the identity by sᵀy / yᵀy
+                let h0 = h.take().unwrap_or_else(|| DMatrix::identity(n, n) * (sy / y.norm_squared()));
+                let left = DMatrix::identity(n, n) - &s * y.transpose() * rho;
+                *h = Some(&left * h0 * left.transpose() + &s * s.transpose() * rho);
+            }
+            Self::Limited { memory, pairs } => {
+                if pairs.len() == *memory {
+                    pairs.pop_front();
+                }
+                pairs.push_back((s, y, rho));
+            }
+        }
+    }
+
+    /// The dense matrix, obtained for L-BFGS by applying it to the unit vectors
+    fn to_matrix(&self, n: usize) -> DMatrix<f64> {
+        match self {
+            Self::Dense(Some(h)) => h.clone(),
+            _ => DMatrix::from_fn(n, n, |i, j| self.apply(&DVector::from_fn(n, |k, _| if k == j { 1.0 } else { 0.0 }))[i]),
+        }
+    }
+}
+
+/// A point along the search direction: `φ(α) = f(x + α d)` and `φ'(α)`
+#[derive(Clone, Copy)]
+struct LinePoint {
+    alpha: f64,
+    value: f64,
+    slope: f64,
+}
+
+/// Finds a step length satisfying the strong Wolfe conditions (Nocedal & Wright, algorithms 3.5 and 3.6)
+fn strong_wolfe(
+    phi: &mut impl FnMut(f64) -> LinePoint,
+    origin: LinePoint,
+    initial: f64,
+    options: &MinimizeOptions,
+) -> Option<LinePoint> {
+    let sufficient = |p: &LinePoint| p.value <= origin.value + options.c1 * p.alpha * origin.slope;
+    let curvature = |p: &LinePoint| p.slope.abs() <= -options.c2 * origin.slope;
+
+    let mut previous = origin;
+    let mut alpha = initial;
+    let mut evaluations = 0;
+    let (mut lo, mut hi) = loop {
+        if evaluations >= options.max_line_search_evaluations {
+            return None;
+        }
+        evaluations += 1;
+        let current = phi(alpha);
+        if !current.value.is_finite() || !sufficient(&current) || (evaluations > 1 && current.value >= previous.value) {
+            break (previous, current);
+        }
+        if curvature(&current) {
+            return Some(current);
+        }
+        if current.slope >= 0.0 {
+            break (current, previous);
+        }
+        previous = current;
+        alpha *= 2.0;
+    };
+
+    // zoom between `lo`, satisfying sufficient decrease, and `hi`
+    while evaluations < options.max_line_search_evaluations {
+        evaluations += 1;
+        let width = hi.alpha - lo.alpha;
+        // minimizer of the quadratic through φ(lo), φ'(lo) and φ(hi), safeguarded by bisection
+        let quadratic = lo.alpha - lo.slope * width * width / (2.0 * (hi.value - lo.value - lo.slope * width));
+        let (a, b) = (lo.alpha + 0.1 * width, hi.alpha - 0.1 * width);
+        let alpha = if quadratic.is_finite() && (a.min(b)..=a.max(b)).contains(&quadratic) {
+            quadratic
+        } else {
+            lo.alpha + 0.5 * width
+        };
+        let current = phi(alpha);
+        if !current.value.is_finite() || !sufficient(&current) || current.value >= lo.value {
+            hi = current;
+        } else {
+            if curvature(&current) {
+                return Some(current);
+            }
+            if current.slope * width >= 0.0 {
+                hi = lo;
+            }
+            lo = current;
+        }
+    }
+    None
+}
+
+/// Minimizes `f` with a quasi-Newton method and a strong Wolfe line search, starting at `x0`
+///
+/// `f` is written against [`Differential`]; its gradients are computed by forward differentiation.
+pub fn minimize(f: impl Fn(&[Differential]) -> Differential, x0: &[f64], options: &MinimizeOptions) -> MinimizeReport {
+    let n = x0.len();
+    let mut x = DVector::from_column_slice(x0);
+    let (mut value, mut gradient) = self::gradient(&f, x0);
+    let mut evaluations = n.max(1);
+    let mut inverse_hessian = InverseHessian::new(options.method);
+    let mut trace = Vec::new();
+
+    let reason = loop {
+        if !value.is_finite() || gradient.iter().any(|g| !g.is_finite()) {
+            break MinimizeStopReason::NonFinite;
+        }
+        if gradient.amax() <= options.gradient_tolerance {
+            break MinimizeStopReason::GradientTolerance;
+        }
+        if trace.len() >= options.max_iterations {
+            break MinimizeStopReason::MaxIterations;
+        }
+
+        let mut direction = -inverse_hessian.apply(&gradient);
+        let mut slope = gradient.dot(&direction);
+        if slope.is_nan() || slope >= 0.0 {
+            // the approximation lost positive definiteness, restart with steepest descent
+            inverse_hessian = InverseHessian::new(options.method);
+            direction = -&gradient;
+            slope = -gradient.norm_squared();
+        }
+        // without curvature information, the first step has unit length
+        let initial = if trace.is_empty() { 1.0 / direction.norm() } else { 1.0 };
+
+        let mut line_search_evaluations = 0;
+        let mut phi = |alpha: f64| {
+            line_search_evaluations += 1;
+            let params: Vec<Differential> = x.iter().zip(direction.iter()).map(|(x, d)| Differential::new(x + alpha * d, *d)).collect();
+            let y = f(&params);
+            LinePoint { alpha, value: y.value, slope: y.derivative }
+        };
+        let origin = LinePoint { alpha: 0.0, value, slope };
+        let accepted = strong_wolfe(&mut phi, origin, initial, options);
+        evaluations += line_search_evaluations;
+        let Some(accepted) = accepted else {
+            break MinimizeStopReason::LineSearchFailed;
+        };
+
+        let next = &x + &direction * accepted.alpha;
+        let (next_value, next_gradient) = self::gradient(&f, next.as_slice());
+        evaluations += n.max(1);
+        inverse_hessian.update(&next - &x, &next_gradient - &gradient);
+        x = next;
+        value = next_value;
+        gradient = next_gradient;
+        trace.push(IterationTrace {
+            value,
+            gradient_norm: gradient.amax(),
+            step_length: accepted.alpha * direction.norm(),
+            line_search_evaluations,
+        });
+    };
+
+    MinimizeReport {
+        x: x.iter().copied().collect(),
+        value,
+        gradient: gradient.iter().copied().collect(),
+        iterations: trace.len(),
+        evaluations,
+        trace,
+        inverse_hessian: options.inverse_hessian.then(|| inverse_hessian.to_matrix(n)),
+        reason,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rosenbrock(x: &[Differential]) -> Differential {
+        let one = Differential::from(1.0);
+        (one - x[0]) * (one - x[0]) + (x[1] - x[0] * x[0]) * (x[1] - x[0] * x[0]) * 100.0
+    }
+
+    #[test]
+    fn exact_gradient() {
+        let (value, g) = gradient(rosenbrock, &[-1.2, 1.0]);
+        assert!((value - 24.2).abs() < 1e-12);
+        assert!((g[0] + 215.6).abs() < 1e-12 && (g[1] + 88.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn minimizes_rosenbrock() {
+        for method in [QuasiNewton::Bfgs, QuasiNewton::Lbfgs { memory: 5 }] {
+            let options = MinimizeOptions { method, ..Default::default() };
+            let report = minimize(rosenbrock, &[-1.2, 1.0], &options);
+            assert!(report.converged(), "{:?}", method);
+            assert!((report.x[0] - 1.0).abs() < 1e-6 && (report.x[1] - 1.0).abs() < 1e-6);
+            assert_eq!(report.trace.len(), report.iterations);
+            assert!(report.iterations < 100);
+            assert_eq!(report.trace.last().unwrap().value, report.value);
+        }
+    }
+
+    #[test]
+    fn inverse_hessian_of_quadratic() {
+        // f = x² + 2y² + xy has the Hessian [[2, 1], [1, 4]], which BFGS recovers in two exact line searches
+        let f = |x: &[Differential]| x[0] * x[0] + x[1] * x[1] * 2.0 + x[0] * x[1];
+        let expected = DMatrix::from_row_slice(2, 2, &[2.0, 1.0, 1.0, 4.0]).try_inverse().unwrap();
+        for method in [QuasiNewton::Bfgs, QuasiNewton::Lbfgs { memory: 5 }] {
+            let options = MinimizeOptions { method, inverse_hessian: true, c2: 1e-6, ..Default::default() };
+            let report = minimize(f, &[3.0, -2.0], &options);
+            assert!(report.converged());
+            let h = report.inverse_hessian.unwrap();
+            assert!((h - &expected).amax() < 1e-6, "{:?}", method);
+        }
+        assert!(minimize(f, &[3.0, -2.0], &Default::default()).inverse_hessian.is_none());
+    }
+
+    #[test]
+    fn counts_every_evaluation() {
+        let calls = core::cell::Cell::new(0);
+        let counted = |x: &[Differential]| {
+            calls.set(calls.get() + 1);
+            rosenbrock(x)
+        };
+        let report = minimize(counted, &[-1.2, 1.0], &Default::default());
+        assert!(report.converged());
+        assert_eq!(report.evaluations, calls.get());
+
+        calls.set(0);
+        let (value, _) = gradient(
+            |_| {
+                calls.set(calls.get() + 1);
+                Differential::from(2.0)
+            },
+            &[],
+        );
+        assert_eq!((value, calls.get()), (2.0, 1));
+    }
+
+    #[test]
+    fn line_search_failure_is_reported() {
+        // unbounded below along x
+        let options = MinimizeOptions { max_line_search_evaluations: 5, ..Default::default() };
+        let report = minimize(|x| -x[0] * 1.0, &[0.0], &options);
+        assert_eq!(report.reason, MinimizeStopReason::LineSearchFailed);
+    }
+}
@@ -0,0 +1,339 @@
+use nalgebra::DVector;
+use num_traits::Float;
+
+use super::trust_region::{trust_region, TrustRegionOptions, TrustRegionStopReason};
+use crate::{jacobian, Differential};
+
+/// A problem `min f(x)` subject to `g(x) = 0` and `h(x) ≤ 0`
+///
+/// The functions are generic over the number type, so they can be evaluated both on
+/// [`Differential`] for the Jacobians and on [`Differential2`](crate::Differential2) for the
+/// Hessians of the Lagrangian.
+pub trait ConstrainedProblem {
+    /// The objective `f(x)`
+    fn objective<T: Float>(&self, x: &[T]) -> T;
+
+    /// The equality constraints `g(x) = 0`
+    fn equalities<T: Float>(&self, _x: &[T]) -> Vec<T> {
+        Vec::new()
+    }
+
+    /// The inequality constraints `h(x) ≤ 0`
+    fn inequalities<T: Float>(&self, _x: &[T]) -> Vec<T> {
+        Vec::new()
+    }
+}
+
+/// Options of [`augmented_lagrangian`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AugmentedLagrangianOptions {
+    /// Stop when all the KKT residuals are at most this
+    pub tolerance: f64,
+
+    /// Maximum number of multiplier updates
+    pub max_iterations: usize,
+
+    /// The initial penalty parameter ρ
+    pub initial_penalty: f64,
+
+    /// The factor by which ρ grows when the constraint violation does not shrink enough
+    pub penalty_growth: f64,
+
+    /// The largest penalty parameter
+    pub max_penalty: f64,
+
+    /// The options of the trust-region minimization of the augmented Lagrangian
+    pub inner: TrustRegionOptions,
+}
+
+impl Default for AugmentedLagrangianOptions {
+    fn default() -> Self {
+        Self {
+            tolerance: 1e-8,
+            max_iterations: 100,
+            initial_penalty: 10.0,
+            penalty_growth: 10.0,
+            max_penalty: 1e12,
+            inner: TrustRegionOptions {
+                gradient_tolerance: 1e-10,
+                ..Default::default()
+            },
+        }
+    }
+}
+
+/// The residuals of the Karush–Kuhn–Tucker conditions
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct KktResiduals {
+    /// `‖∇f + Jgᵀλ + Jhᵀμ‖∞`
+    pub stationarity: f64,
+
+    /// The largest violation of a constraint, `max(|gᵢ|, hⱼ⁺)`
+    pub feasibility: f64,
+
+    /// `max |μⱼ hⱼ|`
+    pub complementarity: f64,
+}
+
+impl KktResiduals {
+    /// The largest of the residuals
+    pub fn max(&self) -> f64 {
+        self.stationarity.max(self.feasibility).max(self.complementarity)
+    }
+}
+
+/// Why [`augmented_lagrangian`] stopped
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConstrainedStopReason {
+    /// The KKT residuals reached the tolerance
+    Converged,
+
+    /// The maximum number of iterations was reached
+    MaxIterations,
+
+    /// The penalty parameter reached its maximum without becoming feasible
+    Infeasible,
+
+    /// The objective or the constraints are not finite
+    NonFinite,
+
+    /// The trust-region minimization of the augmented Lagrangian stopped early, for this reason
+    InnerFailure(TrustRegionStopReason),
+}
+
+/// The result of [`augmented_lagrangian`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConstrainedReport {
+    /// The last iterate
+    pub x: Vec<f64>,
+
+    /// The objective value at `x`
+    pub value: f64,
+
+    /// The multipliers λ of the equality constraints
+    pub equality_multipliers: Vec<f64>,
+
+    /// The multipliers μ ≥ 0 of the inequality constraints, zero for inactive ones
+    pub inequality_multipliers: Vec<f64>,
+
+    /// The KKT residuals at `x` and the multipliers
+    pub kkt: KktResiduals,
+
+    /// The final penalty parameter
+    pub penalty: f64,
+
+    /// The number of multiplier updates
+    pub iterations: usize,
+
+    /// The total number of trust-region iterations
+    pub inner_iterations: usize,
+
+    /// Why the iteration stopped
+    pub reason: ConstrainedStopReason,
+}
+
+impl ConstrainedReport {
+    /// Returns `true` if the KKT residuals reached the tolerance
+    pub fn converged(&self) -> bool {
+        self.reason == ConstrainedStopReason::Converged
+    }
+}
+
+/// The Powell–Hestenes–Rockafellar augmented Lagrangian
+///
+/// `f + λᵀg + ρ/2 ‖g‖² + 1/(2ρ) Σ (max(0, μⱼ + ρ hⱼ)² - μⱼ²)`
+fn augmented<T: Float>(problem: &impl ConstrainedProblem, x: &[T], lambda: &[f64], mu: &[f64], penalty: f64) -> T {
+    let constant = |c: f64| T::from(c).unwrap();
+    let rho = constant(penalty);
+    let half = constant(0.5);
+    let mut value = problem.objective(x);
+    for (g, &l) in problem.equalities(x).into_iter().zip(lambda) {
+        value = value + constant(l) * g + half * rho * g * g;
+    }
+    for (h, &m) in problem.inequalities(x).into_iter().zip(mu) {
+        let shifted = (constant(m) + rho * h).max(T::zero());
+        value = value + half * (shifted * shifted - constant(m * m)) / rho;
+    }
+    value
+}
+
+/// Evaluates the constraints at `x`
+fn constraints(problem: &impl ConstrainedProblem, x: &[f64]) -> (Vec<f64>, Vec<f64>) {
+    (problem.equalities(x), problem.inequalities(x))
+}
+
+/// The KKT residuals at `x` with the multipliers
+fn kkt(problem: &impl ConstrainedProblem, x: &[f64], lambda: &[f64], mu: &[f64]) -> KktResiduals {
+    let (g, h) = constraints(problem, x);
+    let mut stationarity = jacobian(|x| vec![problem.objective(x)], x).row(0).transpose();
+    if !g.is_empty() {
+        stationarity += jacobian(|x: &[Differential]| problem.equalities(x), x).tr_mul(&DVector::from_column_slice(lambda));
+    }
+    if !h.is_empty() {
+        stationarity += jacobian(|x: &[Differential]| problem.inequalities(x), x).tr_mul(&DVector::from_column_slice(mu));
+    }
+    KktResiduals {
+        stationarity: stationarity.amax(),
+        feasibility: violation(&g, &h),
+        complementarity: h.iter().zip(mu).map(|(h, m)| (h * m).abs()).fold(0.0, f64::max),
+    }
+}
+
+/// The largest violation of a constraint
+fn violation(g: &[f64], h: &[f64]) -> f64 {
+    g.iter().map(|g| g.abs()).chain(h.iter().map(|h| h.max(0.0))).fold(0.0, f64::max)
+}
+
+/// Solves a constrained problem with the augmented Lagrangian method, starting at `x0`
+///
+/// Each iteration minimizes the augmented Lagrangian with [`trust_region`], using its exact
+/// Hessians from nested differentials, and then updates the multipliers. The penalty grows when the
+/// constraint violation does not decrease fast enough. If a minimization runs out of iterations or
+/// becomes non-finite, the iteration stops with [`ConstrainedStopReason::InnerFailure`] before
+/// updating the multipliers.
+pub fn augmented_lagrangian(
+    problem: &impl ConstrainedProblem,
+    x0: &[f64],
+    options: &AugmentedLagrangianOptions,
+) -> ConstrainedReport {
+    let mut x = x0.to_vec();
+    let (g, h) = constraints(problem, &x);
+    let mut lambda = vec![0.0; g.len()];
+    let mut mu = vec![0.0; h.len()];
+    let mut penalty = options.initial_penalty;
+    let mut last_violation = violation(&g, &h);
+    let mut iterations = 0;
+    let mut inner_iterations = 0;
+
+    let reason = loop {
+        let value = problem.objective(&x);
+        let (g, h) = constraints(problem, &x);
+        if !value.is_finite() || g.iter().chain(&h).any(|c| !c.is_finite()) {
+            break ConstrainedStopReason::NonFinite;
+        }
+        if iterations > 0 && kkt(problem, &x, &lambda, &mu).max() <= options.tolerance {
+            break ConstrainedStopReason::Converged;
+        }
+        if iterations >= options.max_iterations {
+            break ConstrainedStopReason::MaxIterations;
+        }
+        iterations += 1;
+
+        let inner = trust_region(|x| augmented(problem, x, &lambda, &mu, penalty), &x, &options.inner);
+        inner_iterations += inner.iterations;
+        x = inner.x;
+        // the multiplier updates assume a minimizer of the augmented Lagrangian, which a vanishing
+        // trust region still provides up to rounding
+        if let reason @ (TrustRegionStopReason::MaxIterations | TrustRegionStopReason::NonFinite) = inner.reason {
+            break ConstrainedStopReason::InnerFailure(reason);
+        }
+
+        let (g, h) = constraints(problem, &x);
+        for (l, g) in lambda.iter_mut().zip(&g) {
+            *l += penalty * g;
+        }
+        for (m, h) in mu.iter_mut().zip(&h) {
+            *m = (*m + penalty * h).max(0.0);
+        }
+        let current_violation = violation(&g, &h);
+        if current_violation > options.tolerance && current_violation > 0.25 * last_violation {
+            if penalty >= options.max_penalty {
+                break ConstrainedStopReason::Infeasible;
+            }
+            penalty = (penalty * options.penalty_growth).min(options.max_penalty);
+        }
+        last_violation = current_violation;
+    };
+
+    ConstrainedReport {
+        value: problem.objective(&x),
+        kkt: kkt(problem, &x, &lambda, &mu),
+        x,
+        equality_multipliers: lambda,
+        inequality_multipliers: mu,
+        penalty,
+        iterations,
+        inner_iterations,
+        reason,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `min (x - 1)² + (y - 2)²` on the line `x + y = 1` and in the half-plane `x ≥ x_min`
+    struct Projection {
+        x_min: f64,
+    }
+
+    impl ConstrainedProblem for Projection {
+        fn objective<T: Float>(&self, x: &[T]) -> T {
+            let (one, two) = (T::one(), T::from(2.0).unwrap());
+            (x[0] - one).powi(2) + (x[1] - two).powi(2)
+        }
+
+        fn equalities<T: Float>(&self, x: &[T]) -> Vec<T> {
+            vec![x[0] + x[1] - T::one()]
+        }
+
+        fn inequalities<T: Float>(&self, x: &[T]) -> Vec<T> {
+            vec![T::from(self.x_min).unwrap() - x[0]]
+        }
+    }
+
+    #[test]
+    fn equality_with_inactive_inequality() {
+        let report = augmented_lagrangian(&Projection { x_min: -1.0 }, &[3.0, 3.0], &Default::default());
+        assert!(report.converged(), "{:?}", report);
+        assert!(report.x[0].abs() < 1e-7 && (report.x[1] - 1.0).abs() < 1e-7);
+        // ∇f = (-2, -2) is balanced by λ ∇g = λ (1, 1)
+        assert!((report.equality_multipliers[0] - 2.0).abs() < 1e-6);
+        assert_eq!(report.inequality_multipliers[0], 0.0);
+        assert!(report.kkt.max() <= 1e-8);
+    }
+
+    #[test]
+    fn active_inequality() {
+        let report = augmented_lagrangian(&Projection { x_min: 0.5 }, &[0.0, 0.0], &Default::default());
+        assert!(report.converged(), "{:?}", report);
+        assert!((report.x[0] - 0.5).abs() < 1e-7 && (report.x[1] - 0.5).abs() < 1e-7);
+        // ∇f = (-1, -3) = -λ (1, 1) - μ (-1, 0) gives λ = 3 and μ = 2
+        assert!((report.equality_multipliers[0] - 3.0).abs() < 1e-6);
+        assert!((report.inequality_multipliers[0] - 2.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn inner_failure_is_reported() {
+        let options = AugmentedLagrangianOptions {
+            inner: TrustRegionOptions { max_iterations: 1, ..Default::default() },
+            ..Default::default()
+        };
+        let report = augmented_lagrangian(&Projection { x_min: -1.0 }, &[3.0, 3.0], &options);
+        assert_eq!(report.reason, ConstrainedStopReason::InnerFailure(TrustRegionStopReason::MaxIterations));
+        assert!(!report.converged());
+        assert_eq!((report.iterations, report.inner_iterations), (1, 1));
+        // the multipliers are not updated from the unconverged iterate
+        assert_eq!(report.equality_multipliers, [0.0]);
+    }
+
+    #[test]
+    fn infeasible_constraints() {
+        struct Infeasible;
+
+        impl ConstrainedProblem for Infeasible {
+            fn objective<T: Float>(&self, x: &[T]) -> T {
+                x[0] * x[0]
+            }
+
+            fn inequalities<T: Float>(&self, x: &[T]) -> Vec<T> {
+                vec![x[0] * x[0] + T::one()]
+            }
+        }
+
+        let report = augmented_lagrangian(&Infeasible, &[1.0], &Default::default());
+        assert_eq!(report.reason, ConstrainedStopReason::Infeasible, "{:?}", report);
+        assert_eq!(report.penalty, AugmentedLagrangianOptions::default().max_penalty);
+        assert!(report.kkt.feasibility >= 1.0);
+    }
+}
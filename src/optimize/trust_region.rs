@@ -0,0 +1,426 @@
+use nalgebra::{DMatrix, DVector};
+
+use crate::{Differential, Differential2};
+
+/// Seeds `x` with the first order direction `a` and the second order direction `b`
+///
+/// The result of `f` then holds `f`, `∇f·a`, `∇f·b` and `aᵀ∇²f b`.
+fn seed(x: &[f64], a: impl Fn(usize) -> f64, b: impl Fn(usize) -> f64) -> Vec<Differential2> {
+    x.iter()
+        .enumerate()
+        .map(|(i, &x)| Differential::new(Differential::new(x, a(i)), Differential::new(b(i), 0.0)))
+        .collect()
+}
+
+fn unit(j: usize) -> impl Fn(usize) -> f64 {
+    move |i| if i == j { 1.0 } else { 0.0 }
+}
+
+/// Evaluates `f`, its gradient and its Hessian at `x` with nested differentials
+///
+/// The Hessian takes `n(n+1)/2` evaluations of `f`.
+pub fn hessian(f: impl Fn(&[Differential2]) -> Differential2, x: &[f64]) -> (f64, DVector<f64>, DMatrix<f64>) {
+    let n = x.len();
+    // the value comes with the diagonal entries, except without variables
+    let mut value = if n == 0 { f(&[]).value.value } else { 0.0 };
+    let mut gradient = DVector::zeros(n);
+    let mut hessian = DMatrix::zeros(n, n);
+    for i in 0..n {
+        for j in i..n {
+            let y = f(&seed(x, unit(i), unit(j)));
+            hessian[(i, j)] = y.derivative.derivative;
+            hessian[(j, i)] = y.derivative.derivative;
+            if i == j {
+                gradient[i] = y.value.derivative;
+                value = y.value.value;
+            }
+        }
+    }
+    (value, gradient, hessian)
+}
+
+/// Computes the Hessian-vector product `∇²f(x) v` with nested differentials
+///
+/// The product takes `n` evaluations of `f` and never forms the Hessian.
+pub fn hessian_vector_product(f: impl Fn(&[Differential2]) -> Differential2, x: &[f64], v: &[f64]) -> DVector<f64> {
+    DVector::from_fn(x.len(), |i, _| f(&seed(x, unit(i), |j| v[j])).derivative.derivative)
+}
+
+/// The solver of the trust-region subproblem `min gᵀp + ½pᵀHp` subject to `‖p‖ ≤ Δ`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Subproblem {
+    /// Powell's dogleg, with the full Hessian
+    ///
+    /// Where the Hessian is not positive definite, the step falls back to the Cauchy point.
+    Dogleg,
+
+    /// Steihaug's truncated conjugate gradients, with Hessian-vector products only
+    ///
+    /// Directions of negative curvature are followed to the trust-region boundary.
+    #[default]
+    SteihaugCg,
+}
+
+/// Options of [`trust_region`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TrustRegionOptions {
+    /// The subproblem solver
+    pub subproblem: Subproblem,
+
+    /// Stop when the largest component of the gradient is at most this
+    pub gradient_tolerance: f64,
+
+    /// Stop when the radius falls below this
+    pub radius_tolerance: f64,
+
+    /// The initial trust-region radius
+    pub initial_radius: f64,
+
+    /// The largest trust-region radius
+    pub max_radius: f64,
+
+    /// Steps are accepted when the actual reduction is at least this fraction of the predicted one
+    pub acceptance: f64,
+
+    /// Maximum number of iterations
+    pub max_iterations: usize,
+}
+
+impl Default for TrustRegionOptions {
+    fn default() -> Self {
+        Self {
+            subproblem: Subproblem::default(),
+            gradient_tolerance: 1e-8,
+            radius_tolerance: 1e-14,
+            initial_radius: 1.0,
+            max_radius: 1e8,
+            acceptance: 1e-4,
+            max_iterations: 1000,
+        }
+    }
+}
+
+/// Why [`trust_region`] stopped
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrustRegionStopReason {
+    /// The gradient reached its tolerance
+    GradientTolerance,
+
+    /// The trust region shrank below its tolerance without an acceptable step
+    RadiusTolerance,
+
+    /// The maximum number of iterations was reached
+    MaxIterations,
+
+    /// The objective or its derivatives are not finite
+    NonFinite,
+}
+
+/// The state after an iteration of [`trust_region`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TrustRegionTrace {
+    /// The objective value after the iteration
+    pub value: f64,
+
+    /// The largest absolute component of the gradient before the iteration
+    pub gradient_norm: f64,
+
+    /// The radius used for the step
+    pub radius: f64,
+
+    /// The ratio of the actual to the predicted reduction
+    pub ratio: f64,
+
+    /// Whether the step was accepted
+    pub accepted: bool,
+}
+
+/// The result of [`trust_region`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct TrustRegionReport {
+    /// The last iterate
+    pub x: Vec<f64>,
+
+    /// The objective value at `x`
+    pub value: f64,
+
+    /// The gradient at `x`
+    pub gradient: Vec<f64>,
+
+    /// The number of iterations
+    pub iterations: usize,
+
+    /// The number of evaluations of the objective
+    pub evaluations: usize,
+
+    /// The state after every iteration
+    pub trace: Vec<TrustRegionTrace>,
+
+    /// Why the iteration stopped
+    pub reason: TrustRegionStopReason,
+}
+
+impl TrustRegionReport {
+    /// Returns `true` if the gradient reached its tolerance
+    pub fn converged(&self) -> bool {
+        self.reason == TrustRegionStopReason::GradientTolerance
+    }
+}
+
+/// The objective together with an evaluation counter
+struct Objective<F> {
+    f: F,
+    evaluations: usize,
+}
+
+impl<F> Objective<F>
+where
+    F: Fn(&[Differential2]) -> Differential2,
+{
+    fn value(&mut self, x: &[f64]) -> f64 {
+        self.evaluations += 1;
+        (self.f)(&seed(x, |_| 0.0, |_| 0.0)).value.value
+    }
+
+    /// The value and the gradient, taking `n` evaluations
+    fn gradient(&mut self, x: &[f64]) -> (f64, DVector<f64>) {
+        self.evaluations += x.len().max(1);
+        // the value comes with the partial derivatives, except without variables
+        let mut value = if x.is_empty() { (self.f)(&[]).value.value } else { 0.0 };
+        let gradient = DVector::from_fn(x.len(), |i, _| {
+            let y = (self.f)(&seed(x, unit(i), |_| 0.0)).value;
+            value = y.value;
+            y.derivative
+        });
+        (value, gradient)
+    }
+
+    fn hessian(&mut self, x: &[f64]) -> DMatrix<f64> {
+        self.evaluations += (x.len() * (x.len() + 1) / 2).max(1);
+        hessian(&self.f, x).2
+    }
+
+    fn hessian_vector_product(&mut self, x: &[f64], v: &DVector<f64>) -> DVector<f64> {
+        self.evaluations += x.len();
+        hessian_vector_product(&self.f, x, v.as_slice())
+    }
+}
+
+/// The `τ ≥ 0` with `‖p + τ d‖ = Δ`
+fn to_boundary(p: &DVector<f64>, d: &DVector<f64>, radius: f64) -> f64 {
+    let (a, b, c) = (d.norm_squared(), 2.0 * p.dot(d), p.norm_squared() - radius * radius);
+    (-b + (b * b - 4.0 * a * c).max(0.0).sqrt()) / (2.0 * a)
+}
+
+/// Powell's dogleg step
+fn dogleg(g: &DVector<f64>, h: &DMatrix<f64>, radius: f64) -> DVector<f64> {
+    let curvature = g.dot(&(h * g));
+    let cauchy = if curvature > 0.0 {
+        g * (-g.norm_squared() / curvature)
+    } else {
+        // negative curvature along -g: go to the boundary
+        g * (-radius / g.norm())
+    };
+    let cauchy_norm = cauchy.norm();
+    if cauchy_norm >= radius {
+        return cauchy * (radius / cauchy_norm);
+    }
+    let Some(newton) = h.clone().cholesky().map(|c| -c.solve(g)) else {
+        return cauchy;
+    };
+    if newton.norm() <= radius {
+        return newton;
+    }
+    let d = &newton - &cauchy;
+    let tau = to_boundary(&cauchy, &d, radius);
+    cauchy + d * tau
+}
+
+/// Steihaug's truncated conjugate gradient step, with `hv` the Hessian-vector product
+fn steihaug_cg(g: &DVector<f64>, mut hv: impl FnMut(&DVector<f64>) -> DVector<f64>, radius: f64) -> DVector<f64> {
+    let tolerance = g.norm().sqrt().min(0.5) * g.norm();
+    let mut p = DVector::zeros(g.len());
+    let mut r = g.clone();
+    let mut d = -g;
+    for _ in 0..g.len().max(1) {
+        let hd = hv(&d);
+        let curvature = d.dot(&hd);
+        if curvature <= 0.0 {
+            let tau = to_boundary(&p, &d, radius);
+            return p + d * tau;
+        }
+        let alpha = r.norm_squared() / curvature;
+        let next = &p + &d * alpha;
+        if next.norm() >= radius {
+            let tau = to_boundary(&p, &d, radius);
+            return p + d * tau;
+        }
+        let next_r = &r + hd * alpha;
+        p = next;
+        if next_r.norm() <= tolerance {
+            break;
+        }
+        let beta = next_r.norm_squared() / r.norm_squared();
+        d = -&next_r + d * beta;
+        r = next_r;
+    }
+    p
+}
+
+/// Minimizes `f` with a trust-region Newton method using exact second derivatives, starting at `x0`
+///
+/// `f` is written against the nested [`Differential2`]; the easiest is a generic objective over
+/// `T: Float` that is also usable with plain numbers. Dogleg steps use the full Hessian, while
+/// Steihaug–CG steps only need Hessian-vector products.
+pub fn trust_region(f: impl Fn(&[Differential2]) -> Differential2, x0: &[f64], options: &TrustRegionOptions) -> TrustRegionReport {
+    let mut objective = Objective { f, evaluations: 0 };
+    let mut x = DVector::from_column_slice(x0);
+    let (mut value, mut gradient) = objective.gradient(x0);
+    let mut hessian = None;
+    let mut radius = options.initial_radius;
+    let mut trace = Vec::new();
+
+    let reason = loop {
+        if !value.is_finite() || gradient.iter().any(|g| !g.is_finite()) {
+            break TrustRegionStopReason::NonFinite;
+        }
+        if gradient.amax() <= options.gradient_tolerance {
+            break TrustRegionStopReason::GradientTolerance;
+        }
+        if radius < options.radius_tolerance {
+            break TrustRegionStopReason::RadiusTolerance;
+        }
+        if trace.len() >= options.max_iterations {
+            break TrustRegionStopReason::MaxIterations;
+        }
+
+        // the step and its predicted reduction -(gᵀp + ½pᵀHp)
+        let (step, predicted) = match options.subproblem {
+            Subproblem::Dogleg => {
+                let h = hessian.get_or_insert_with(|| objective.hessian(x.as_slice()));
+                if h.iter().any(|h| !h.is_finite()) {
+                    break TrustRegionStopReason::NonFinite;
+                }
+                let step = dogleg(&gradient, h, radius);
+                let predicted = -(gradient.dot(&step) + 0.5 * step.dot(&(&*h * &step)));
+                (step, predicted)
+            }
+            Subproblem::SteihaugCg => {
+                let step = steihaug_cg(&gradient, |v| objective.hessian_vector_product(x.as_slice(), v), radius);
+                let hp = objective.hessian_vector_product(x.as_slice(), &step);
+                (step.clone(), -(gradient.dot(&step) + 0.5 * step.dot(&hp)))
+            }
+        };
+
+        let candidate = &x + &step;
+        let candidate_value = objective.value(candidate.as_slice());
+        let ratio = if predicted > 0.0 { (value - candidate_value) / predicted } else { f64::NEG_INFINITY };
+        let ratio = if ratio.is_nan() { f64::NEG_INFINITY } else { ratio };
+
+        let step_radius = radius;
+        if ratio < 0.25 {
+            radius = 0.25 * step.norm();
+        } else if ratio > 0.75 && step.norm() >= 0.99 * radius {
+            radius = (2.0 * radius).min(options.max_radius);
+        }
+        let accepted = ratio > options.acceptance;
+        if accepted {
+            x = candidate;
+            (value, gradient) = objective.gradient(x.as_slice());
+            hessian = None;
+        }
+        trace.push(TrustRegionTrace {
+            value,
+            gradient_norm: gradient.amax(),
+            radius: step_radius,
+            ratio,
+            accepted,
+        });
+    };
+
+    TrustRegionReport {
+        x: x.iter().copied().collect(),
+        value,
+        gradient: gradient.iter().copied().collect(),
+        iterations: trace.len(),
+        evaluations: objective.evaluations,
+        trace,
+        reason,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use num_traits::Float;
+
+    use super::*;
+    use crate::optimize::{minimize, MinimizeOptions};
+
+    fn rosenbrock<T: Float>(x: &[T]) -> T {
+        let hundred = T::from(100.0).unwrap();
+        (T::one() - x[0]).powi(2) + hundred * (x[1] - x[0] * x[0]).powi(2)
+    }
+
+    #[test]
+    fn exact_second_derivatives() {
+        let (value, g, h) = hessian(rosenbrock, &[1.0, 2.0]);
+        assert_eq!(value, 100.0);
+        assert_eq!(g, DVector::from_vec(vec![-400.0, 200.0]));
+        assert_eq!(h, DMatrix::from_row_slice(2, 2, &[402.0, -400.0, -400.0, 200.0]));
+        let hv = hessian_vector_product(rosenbrock, &[1.0, 2.0], &[1.0, 1.0]);
+        assert_eq!(hv, DVector::from_vec(vec![2.0, -200.0]));
+    }
+
+    #[test]
+    fn minimizes_rosenbrock() {
+        for subproblem in [Subproblem::Dogleg, Subproblem::SteihaugCg] {
+            let options = TrustRegionOptions { subproblem, ..Default::default() };
+            let report = trust_region(rosenbrock, &[-1.2, 1.0], &options);
+            assert!(report.converged(), "{:?}", subproblem);
+            assert!((report.x[0] - 1.0).abs() < 1e-8 && (report.x[1] - 1.0).abs() < 1e-8);
+            assert!(report.iterations < 60, "{:?} {}", subproblem, report.iterations);
+            assert_eq!(report.trace.len(), report.iterations);
+        }
+    }
+
+    #[test]
+    fn counts_every_evaluation() {
+        for subproblem in [Subproblem::Dogleg, Subproblem::SteihaugCg] {
+            let calls = core::cell::Cell::new(0);
+            let f = |x: &[Differential2]| {
+                calls.set(calls.get() + 1);
+                rosenbrock(x)
+            };
+            let report = trust_region(f, &[-1.2, 1.0], &TrustRegionOptions { subproblem, ..Default::default() });
+            assert_eq!(report.evaluations, calls.get(), "{:?}", subproblem);
+        }
+    }
+
+    #[test]
+    fn badly_scaled_objective() {
+        // curvatures differing by ten orders of magnitude
+        fn f<T: Float>(x: &[T]) -> T {
+            let scale = T::from(1e10).unwrap();
+            x[0].powi(2) * scale + x[1].powi(2) * scale.recip() + (x[0] * x[1]).sin().powi(2)
+        }
+        let exact = trust_region(f, &[1.0, 1e5], &TrustRegionOptions { subproblem: Subproblem::Dogleg, initial_radius: 1e5, ..Default::default() });
+        assert!(exact.converged());
+        let quasi = minimize(f, &[1.0, 1e5], &MinimizeOptions::default());
+        assert!(exact.iterations < 10);
+        assert!(!quasi.converged() || exact.iterations < quasi.iterations);
+    }
+
+    #[test]
+    fn negative_curvature_is_escaped() {
+        // a saddle at the origin with minima at (±1, 0)
+        fn f<T: Float>(x: &[T]) -> T {
+            let quarter = T::from(0.25).unwrap();
+            x[0].powi(4) * quarter - x[0].powi(2) * T::from(0.5).unwrap() + x[1].powi(2)
+        }
+        for subproblem in [Subproblem::Dogleg, Subproblem::SteihaugCg] {
+            let report = trust_region(f, &[1e-3, 1.0], &TrustRegionOptions { subproblem, ..Default::default() });
+            assert!(report.converged());
+            assert!((report.x[0] - 1.0).abs() < 1e-6 && report.x[1].abs() < 1e-6, "{:?} {:?}", subproblem, report.x);
+        }
+    }
+}
@@ -0,0 +1,373 @@
+/*!
+Seeded conversions into differential form.
+
+[`seed`] converts a parameter object with `N` scalar components into its differential form with
+`Differential<T, SVector<T, N>>` components, where component `i` is seeded with the unit tangent
+`e_i`. After evaluating a function on the seeded parameters, [`value_and_jacobian`] splits the
+outputs into their real form and the Jacobian matrix:
+
+```
+use differential::seed::{seed, value_and_jacobian};
+use nalgebra::Vector2;
+
+let (mass, position) = seed::<f64, _, 3>((2.0, Vector2::new(1.0, 3.0)));
+let (value, jacobian) = value_and_jacobian(position * mass);
+assert_eq!(value, Vector2::new(2.0, 6.0));
+assert_eq!(jacobian.row(1).iter().copied().collect::<Vec<_>>(), vec![3.0, 0.0, 2.0]);
+```
+
+The components are numbered in order: tuple and array elements in order, matrices in column-major
+order.
+
+Structs with `#[derive(IntoDifferentialForm)]` cannot be seeded, because the fields of their
+generated differential struct have scalar derivatives. Seed a tuple of their fields instead:
+
+```compile_fail
+use differential::seed::seed;
+use differential_derive::IntoDifferentialForm;
+
+#[derive(IntoDifferentialForm)]
+struct Spring {
+    k: f64,
+    rest_length: f64,
+}
+
+let spring = seed::<f64, _, 2>(Spring { k: 2.0, rest_length: 1.0 });
+```
+*/
+
+use nalgebra::{allocator::Allocator, DMatrix, DefaultAllocator, Dim, DimName, Matrix, OMatrix, OPoint, RawStorage, SVector, Scalar};
+
+use crate::{Differential, IntoRealForm};
+
+/// Converts the type into its differential form, seeding its components with unit tangents of
+/// `SVector<T, N>`
+pub trait IntoSeededForm<T: Scalar, const N: usize> {
+    /// The output type
+    type SeededForm;
+
+    /// The number of scalar components
+    fn components(&self) -> usize;
+
+    /// Converts the type, seeding its components with `e_offset`, `e_(offset+1)`, …
+    ///
+    /// # Panics
+    ///
+    /// If the seeded components do not fit into `N` tangent directions.
+    fn into_seeded_form_at(self, offset: usize) -> Self::SeededForm;
+}
+
+/// Outputs computed from seeded parameters, whose tangents form the rows of a Jacobian
+pub trait SeededOutput<T: Scalar, const N: usize>: IntoRealForm {
+    /// Appends the tangent of every scalar component, in component order
+    fn push_tangents(&self, tangents: &mut Vec<SVector<T, N>>);
+}
+
+/// Seeds every scalar component of `params` with its own unit tangent direction
+///
+/// # Panics
+///
+/// If `params` does not have exactly `N` scalar components.
+pub fn seed<T, P, const N: usize>(params: P) -> P::SeededForm
+where
+    T: Scalar,
+    P: IntoSeededForm<T, N>,
+{
+    assert_eq!(params.components(), N, "the number of components must match the tangent dimension");
+    params.into_seeded_form_at(0)
+}
+
+/// Splits outputs of seeded parameters into their real form and the Jacobian matrix
+///
+/// The Jacobian has one row per scalar output component and one column per parameter component.
+pub fn value_and_jacobian<T, O, const N: usize>(outputs: O) -> (O::RealForm, DMatrix<T>)
+where
+    T: Scalar + num_traits::Zero,
+    O: SeededOutput<T, N>,
+{
+    let mut tangents = Vec::new();
+    outputs.push_tangents(&mut tangents);
+    let jacobian = DMatrix::from_fn(tangents.len(), N, |i, j| tangents[i][j].clone());
+    (outputs.into_real_form(), jacobian)
+}
+
+macro_rules! scalar_seeds {
+    ($($t:ty),*) => {
+        $(
+            impl<const N: usize> IntoSeededForm<$t, N> for $t {
+                type SeededForm = Differential<$t, SVector<$t, N>>;
+
+                fn components(&self) -> usize {
+                    1
+                }
+
+                fn into_seeded_form_at(self, offset: usize) -> Self::SeededForm {
+                    let mut tangent = SVector::zeros();
+                    tangent[offset] = 1.0;
+                    Differential::new(self, tangent)
+                }
+            }
+        )*
+    };
+}
+
+scalar_seeds!(f32, f64);
+
+impl<T, const N: usize> SeededOutput<T, N> for Differential<T, SVector<T, N>>
+where
+    T: Scalar,
+{
+    fn push_tangents(&self, tangents: &mut Vec<SVector<T, N>>) {
+        tangents.push(self.derivative.clone());
+    }
+}
+
+impl<S, T, R, C, St, const N: usize> IntoSeededForm<S, N> for Matrix<T, R, C, St>
+where
+    S: Scalar,
+    T: Scalar + IntoSeededForm<S, N>,
+    T::SeededForm: Scalar,
+    R: Dim,
+    C: Dim,
+    St: RawStorage<T, R, C>,
+    DefaultAllocator: Allocator<T::SeededForm, R, C>,
+{
+    type SeededForm = OMatrix<T::SeededForm, R, C>;
+
+    fn components(&self) -> usize {
+        self.iter().map(T::components).sum()
+    }
+
+    fn into_seeded_form_at(self, mut offset: usize) -> Self::SeededForm {
+        self.map(|x| {
+            let components = x.components();
+            let seeded = x.into_seeded_form_at(offset);
+            offset += components;
+            seeded
+        })
+    }
+}
+
+impl<S, T, R, C, St, const N: usize> SeededOutput<S, N> for Matrix<T, R, C, St>
+where
+    S: Scalar,
+    T: Scalar + SeededOutput<S, N>,
+    T::RealForm: Scalar,
+    R: Dim,
+    C: Dim,
+    St: RawStorage<T, R, C>,
+    DefaultAllocator: Allocator<T::RealForm, R, C>,
+{
+    fn push_tangents(&self, tangents: &mut Vec<SVector<S, N>>) {
+        self.iter().for_each(|x| x.push_tangents(tangents));
+    }
+}
+
+impl<S, T, D, const N: usize> IntoSeededForm<S, N> for OPoint<T, D>
+where
+    S: Scalar,
+    T: Scalar + IntoSeededForm<S, N>,
+    T::SeededForm: Scalar,
+    D: DimName,
+    DefaultAllocator: Allocator<T, D> + Allocator<T::SeededForm, D>,
+{
+    type SeededForm = OPoint<T::SeededForm, D>;
+
+    fn components(&self) -> usize {
+        self.coords.components()
+    }
+
+    fn into_seeded_form_at(self, offset: usize) -> Self::SeededForm {
+        OPoint::from(self.coords.into_seeded_form_at(offset))
+    }
+}
+
+impl<S, T, D, const N: usize> SeededOutput<S, N> for OPoint<T, D>
+where
+    S: Scalar,
+    T: Scalar + SeededOutput<S, N>,
+    T::RealForm: Scalar,
+    D: DimName,
+    DefaultAllocator: Allocator<T, D> + Allocator<T::RealForm, D>,
+{
+    fn push_tangents(&self, tangents: &mut Vec<SVector<S, N>>) {
+        self.coords.push_tangents(tangents);
+    }
+}
+
+impl<S, T, const M: usize, const N: usize> IntoSeededForm<S, N> for [T; M]
+where
+    S: Scalar,
+    T: IntoSeededForm<S, N>,
+{
+    type SeededForm = [T::SeededForm; M];
+
+    fn components(&self) -> usize {
+        self.iter().map(T::components).sum()
+    }
+
+    fn into_seeded_form_at(self, mut offset: usize) -> Self::SeededForm {
+        self.map(|x| {
+            let components = x.components();
+            let seeded = x.into_seeded_form_at(offset);
+            offset += components;
+            seeded
+        })
+    }
+}
+
+impl<S, T, const M: usize, const N: usize> SeededOutput<S, N> for [T; M]
+where
+    S: Scalar,
+    T: SeededOutput<S, N>,
+{
+    fn push_tangents(&self, tangents: &mut Vec<SVector<S, N>>) {
+        self.iter().for_each(|x| x.push_tangents(tangents));
+    }
+}
+
+impl<S, T, const N: usize> IntoSeededForm<S, N> for Vec<T>
+where
+    S: Scalar,
+    T: IntoSeededForm<S, N>,
+{
+    type SeededForm = Vec<T::SeededForm>;
+
+    fn components(&self) -> usize {
+        self.iter().map(T::components).sum()
+    }
+
+    fn into_seeded_form_at(self, mut offset: usize) -> Self::SeededForm {
+        self.into_iter()
+            .map(|x| {
+                let components = x.components();
+                let seeded = x.into_seeded_form_at(offset);
+                offset += components;
+                seeded
+            })
+            .collect()
+    }
+}
+
+impl<S, T, const N: usize> SeededOutput<S, N> for Vec<T>
+where
+    S: Scalar,
+    T: SeededOutput<S, N>,
+{
+    fn push_tangents(&self, tangents: &mut Vec<SVector<S, N>>) {
+        self.iter().for_each(|x| x.push_tangents(tangents));
+    }
+}
+
+macro_rules! tuple_seeds {
+    ($(($($t:ident $i:tt),+)),*) => {
+        $(
+            impl<S, $($t),+, const N: usize> IntoSeededForm<S, N> for ($($t,)+)
+            where
+                S: Scalar,
+                $($t: IntoSeededForm<S, N>),+
+            {
+                type SeededForm = ($($t::SeededForm,)+);
+
+                fn components(&self) -> usize {
+                    0 $(+ self.$i.components())+
+                }
+
+                #[allow(unused_assignments)]
+                fn into_seeded_form_at(self, mut offset: usize) -> Self::SeededForm {
+                    ($({
+                        let components = self.$i.components();
+                        let seeded = self.$i.into_seeded_form_at(offset);
+                        offset += components;
+                        seeded
+                    },)+)
+                }
+            }
+
+            impl<S, $($t),+, const N: usize> SeededOutput<S, N> for ($($t,)+)
+            where
+                S: Scalar,
+                $($t: SeededOutput<S, N>),+
+            {
+                fn push_tangents(&self, tangents: &mut Vec<SVector<S, N>>) {
+                    $(self.$i.push_tangents(tangents);)+
+                }
+            }
+        )*
+    };
+}
+
+tuple_seeds!(
+    (A 0),
+    (A 0, B 1),
+    (A 0, B 1, C 2),
+    (A 0, B 1, C 2, D 3),
+    (A 0, B 1, C 2, D 3, E 4),
+    (A 0, B 1, C 2, D 3, E 4, F 5),
+    (A 0, B 1, C 2, D 3, E 4, F 5, G 6),
+    (A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7)
+);
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::{Matrix3x2, Point2, Vector3, Vector5};
+
+    use super::*;
+
+    #[test]
+    fn seeds_unit_tangents() {
+        let x = seed::<f64, _, 3>(Vector3::new(1.0, 2.0, 3.0));
+        assert_eq!(x.y, Differential::new(2.0, Vector3::new(0.0, 1.0, 0.0)));
+
+        let (scale, point, weights) = seed::<f64, _, 5>((2.0, Point2::new(3.0, 4.0), [5.0, 6.0]));
+        assert_eq!(scale.derivative, Vector5::new(1.0, 0.0, 0.0, 0.0, 0.0));
+        assert_eq!(point.y.derivative, Vector5::new(0.0, 0.0, 1.0, 0.0, 0.0));
+        assert_eq!(weights[1].derivative, Vector5::new(0.0, 0.0, 0.0, 0.0, 1.0));
+    }
+
+    #[test]
+    #[should_panic(expected = "the number of components must match the tangent dimension")]
+    fn seed_dimension_mismatch() {
+        seed::<f64, _, 2>(Vector3::new(1.0, 2.0, 3.0));
+    }
+
+    #[test]
+    fn structured_jacobian() {
+        let (scale, point) = seed::<f64, _, 3>((2.0, Point2::new(3.0, 4.0)));
+        let outputs = (point.x * scale, vec![point.y * point.x, point.y]);
+        let (value, jacobian) = value_and_jacobian(outputs);
+        assert_eq!(value, (6.0, vec![12.0, 4.0]));
+        assert_eq!(jacobian, DMatrix::from_row_slice(3, 3, &[3.0, 2.0, 0.0, 0.0, 4.0, 3.0, 0.0, 0.0, 1.0]));
+
+        let m = seed::<f64, _, 2>([1.0, 2.0]);
+        let (_, jacobian) = value_and_jacobian(Matrix3x2::new(m[0], m[1], m[1], m[0], m[0] * m[1], m[0]));
+        // column-major output order
+        assert_eq!(jacobian.row(2), DMatrix::from_row_slice(1, 2, &[2.0, 1.0]).row(0));
+    }
+
+    #[test]
+    fn derived_structs_through_their_fields() {
+        use differential_derive::{IntoDifferentialForm, IntoRealForm};
+
+        use crate::IntoDifferentialForm as _;
+
+        #[derive(Debug, Clone, PartialEq, IntoDifferentialForm, IntoRealForm)]
+        struct Spring {
+            k: f64,
+            rest_length: f64,
+        }
+
+        let spring = Spring { k: 2.0, rest_length: 1.0 };
+        let (k, rest_length) = seed::<f64, _, 2>((spring.k, spring.rest_length));
+        // the energy of the spring stretched to a length of 3
+        let stretch = Differential::from(3.0) - rest_length;
+        let (energy, jacobian) = value_and_jacobian(k * stretch * stretch * 0.5);
+        assert_eq!(energy, 4.0);
+        assert_eq!(jacobian, DMatrix::from_row_slice(1, 2, &[2.0, -4.0]));
+
+        // the unseeded differential form has scalar derivatives
+        let dual: SpringDifferential = spring.clone().into_differential_form();
+        assert_eq!(dual.k, Differential::new(2.0, 0.0));
+        assert_eq!(dual.into_real_form(), spring);
+    }
+}
@@ -0,0 +1,335 @@
+/*!
+Root finders driven by [`Differential`].
+
+The functions are evaluated on differentials, so every evaluation yields both the residual and its
+derivative. [`newton_1d`] solves scalar equations and [`newton`] (with the `nalgebra` feature)
+solves nonlinear systems. [`continuation`] traces how the solutions of a system change with a
+parameter.
+*/
+
+use crate::Differential;
+
+#[cfg(feature = "nalgebra")]
+mod continuation;
+#[cfg(feature = "nalgebra")]
+mod system;
+#[cfg(feature = "nalgebra")]
+pub use continuation::{
+    continuation, BranchPoint, ContinuationOptions, ContinuationReport, ContinuationStopReason, SpecialPoint,
+    SpecialPointKind, Stability,
+};
+#[cfg(feature = "nalgebra")]
+pub use system::{newton, LinearSolver, NewtonOptions, NewtonReport, NewtonStopReason};
+
+/// Options of [`newton_1d`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Newton1dOptions {
+    /// Stop when `|f(x)|` is at most this
+    pub tolerance: f64,
+
+    /// Stop when a step is at most `step_tolerance * (1 + |x|)`
+    pub step_tolerance: f64,
+
+    /// Maximum number of iterations
+    pub max_iterations: usize,
+
+    /// Factor by which a Newton step is shortened when it does not decrease `|f|`
+    pub damping: f64,
+
+    /// Maximum number of times a single step is shortened
+    pub max_backtracks: usize,
+
+    /// An interval `(a, b)` where `f` changes sign
+    ///
+    /// Newton steps are kept inside the bracket and replaced by bisection when they leave it or
+    /// converge slowly. A bracket is also detected on the fly when two iterates have residuals of
+    /// opposite signs. A bracket without a sign change is ignored.
+    pub bracket: Option<(f64, f64)>,
+}
+
+impl Default for Newton1dOptions {
+    fn default() -> Self {
+        Self {
+            tolerance: 1e-12,
+            step_tolerance: 1e-15,
+            max_iterations: 100,
+            damping: 0.5,
+            max_backtracks: 30,
+            bracket: None,
+        }
+    }
+}
+
+/// Why [`newton_1d`] stopped
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StopReason {
+    /// `|f(x)|` reached the tolerance
+    ResidualTolerance,
+
+    /// The step reached the step tolerance
+    StepTolerance,
+
+    /// The maximum number of iterations was reached
+    MaxIterations,
+
+    /// The derivative vanished without a bracket to fall back to
+    ZeroDerivative,
+
+    /// `f` or its derivative is not finite
+    NonFinite,
+}
+
+/// The kind of a step taken by [`newton_1d`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StepKind {
+    /// A full Newton step
+    Newton,
+
+    /// A Newton step shortened by damping
+    Damped,
+
+    /// A bisection step of the bracket
+    Bisection,
+}
+
+/// The result of [`newton_1d`] with convergence diagnostics
+#[derive(Debug, Clone, PartialEq)]
+pub struct Newton1dReport {
+    /// The last iterate
+    pub root: f64,
+
+    /// `f(root)`
+    pub residual: f64,
+
+    /// `f'(root)`
+    pub derivative: f64,
+
+    /// The number of iterations
+    pub iterations: usize,
+
+    /// `|f(x)|` of the initial point and of every iterate
+    pub residual_history: Vec<f64>,
+
+    /// The kind of every step
+    pub steps: Vec<StepKind>,
+
+    /// The final bracket, if one was known
+    pub bracket: Option<(f64, f64)>,
+
+    /// Why the iteration stopped
+    pub reason: StopReason,
+}
+
+impl Newton1dReport {
+    /// Returns `true` if a tolerance was reached
+    pub fn converged(&self) -> bool {
+        matches!(self.reason, StopReason::ResidualTolerance | StopReason::StepTolerance)
+    }
+}
+
+/// A point where `f` was evaluated
+#[derive(Clone, Copy)]
+struct Evaluation {
+    x: f64,
+    f: f64,
+    df: f64,
+}
+
+impl Evaluation {
+    fn new(f: &impl Fn(Differential) -> Differential, x: f64) -> Self {
+        let y = f(Differential::new(x, 1.0));
+        Self { x, f: y.value, df: y.derivative }
+    }
+
+    fn is_finite(&self) -> bool {
+        self.f.is_finite() && self.df.is_finite()
+    }
+}
+
+/// A sign-changing interval, stored with the sign of `f` at its lower end
+#[derive(Clone, Copy)]
+struct Bracket {
+    lo: f64,
+    hi: f64,
+    lo_negative: bool,
+}
+
+impl Bracket {
+    fn new(a: f64, fa: f64, b: f64, fb: f64) -> Option<Self> {
+        let (lo, hi, flo) = if a <= b { (a, b, fa) } else { (b, a, fb) };
+        let sign_change = (fa <= 0.0 && fb >= 0.0) || (fa >= 0.0 && fb <= 0.0);
+        sign_change.then_some(Self { lo, hi, lo_negative: flo < 0.0 })
+    }
+
+    /// Shrinks the bracket to the side of `x` where `f` still changes sign
+    fn update(&mut self, x: f64, fx: f64) {
+        if (fx < 0.0) == self.lo_negative {
+            self.lo = x;
+        } else {
+            self.hi = x;
+        }
+    }
+
+    fn contains(&self, x: f64) -> bool {
+        self.lo < x && x < self.hi
+    }
+
+    fn mid(&self) -> f64 {
+        self.lo + 0.5 * (self.hi - self.lo)
+    }
+}
+
+/// Finds a root of `f` with a safeguarded Newton–Raphson iteration starting at `x0`
+///
+/// `f` is evaluated on a differential, which yields `f(x)` and `f'(x)` at once. Steps that do not
+/// decrease `|f|` are damped. When a bracket is known, steps leaving it or converging slowly are
+/// replaced by bisection, so the iteration cannot diverge.
+pub fn newton_1d(f: impl Fn(Differential) -> Differential, x0: f64, options: &Newton1dOptions) -> Newton1dReport {
+    let mut current = Evaluation::new(&f, x0);
+    let mut bracket = options.bracket.and_then(|(a, b)| {
+        let (ea, eb) = (Evaluation::new(&f, a), Evaluation::new(&f, b));
+        Bracket::new(a, ea.f, b, eb.f)
+    });
+    if let Some(bracket) = &bracket {
+        if !bracket.contains(x0) {
+            current = Evaluation::new(&f, bracket.mid());
+        }
+    }
+
+    let mut residual_history = vec![current.f.abs()];
+    let mut steps = Vec::new();
+    let mut last_step = f64::INFINITY;
+    let mut iterations = 0;
+
+    let reason = loop {
+        if !current.is_finite() {
+            break StopReason::NonFinite;
+        }
+        if current.f.abs() <= options.tolerance {
+            break StopReason::ResidualTolerance;
+        }
+        if iterations >= options.max_iterations {
+            break StopReason::MaxIterations;
+        }
+        iterations += 1;
+
+        let newton_step = -current.f / current.df;
+        let (next, kind) = match &bracket {
+            Some(b) => {
+                // bisect when Newton leaves the bracket or does not halve the previous step
+                let newton = current.x + newton_step;
+                if newton_step.is_finite() && b.contains(newton) && newton_step.abs() <= 0.5 * last_step.abs() {
+                    (Evaluation::new(&f, newton), StepKind::Newton)
+                } else {
+                    (Evaluation::new(&f, b.mid()), StepKind::Bisection)
+                }
+            }
+            None => {
+                if current.df == 0.0 || !newton_step.is_finite() {
+                    break StopReason::ZeroDerivative;
+                }
+                damped_step(&f, current, newton_step, options)
+            }
+        };
+
+        let step = next.x - current.x;
+        bracket = match bracket {
+            Some(mut b) => {
+                b.update(next.x, next.f);
+                Some(b)
+            }
+            None => Bracket::new(current.x, current.f, next.x, next.f),
+        };
+        current = next;
+        last_step = step;
+        residual_history.push(current.f.abs());
+        steps.push(kind);
+
+        if current.is_finite() && current.f.abs() > options.tolerance && step.abs() <= options.step_tolerance * (1.0 + current.x.abs()) {
+            break StopReason::StepTolerance;
+        }
+    };
+
+    Newton1dReport {
+        root: current.x,
+        residual: current.f,
+        derivative: current.df,
+        iterations,
+        residual_history,
+        steps,
+        bracket: bracket.map(|b| (b.lo, b.hi)),
+        reason,
+    }
+}
+
+/// Shortens the Newton step until `|f|` decreases
+///
+/// If no shortened step decreases `|f|`, the shortest one is taken.
+fn damped_step(
+    f: &impl Fn(Differential) -> Differential,
+    current: Evaluation,
+    newton_step: f64,
+    options: &Newton1dOptions,
+) -> (Evaluation, StepKind) {
+    let mut scale = 1.0;
+    let mut next = Evaluation::new(f, current.x + newton_step);
+    for _ in 0..options.max_backtracks {
+        if next.is_finite() && next.f.abs() < current.f.abs() {
+            break;
+        }
+        scale *= options.damping;
+        next = Evaluation::new(f, current.x + scale * newton_step);
+    }
+    let kind = if scale == 1.0 { StepKind::Newton } else { StepKind::Damped };
+    (next, kind)
+}
+
+#[cfg(test)]
+mod tests {
+    use num_traits::Float;
+
+    use super::*;
+
+    #[test]
+    fn quadratic_convergence() {
+        let report = newton_1d(|x| x * x - Differential::from(2.0), 1.0, &Newton1dOptions::default());
+        assert_eq!(report.reason, StopReason::ResidualTolerance);
+        assert!((report.root - 2f64.sqrt()).abs() < 1e-12);
+        assert!(report.iterations <= 6);
+        assert_eq!(report.residual_history.len(), report.iterations + 1);
+        assert!(report.steps.iter().all(|&kind| kind == StepKind::Newton));
+    }
+
+    #[test]
+    fn damping_prevents_overshoot() {
+        // plain Newton on atan diverges from x0 = 2
+        let report = newton_1d(|x| x.atan(), 2.0, &Newton1dOptions::default());
+        assert!(report.converged());
+        assert!(report.root.abs() < 1e-12);
+        assert!(report.steps.contains(&StepKind::Damped));
+    }
+
+    #[test]
+    fn bracket_falls_back_to_bisection() {
+        // plain Newton cycles between 0 and 1 on this cubic
+        let f = |x: Differential| x * x * x - x * Differential::from(2.0) + Differential::from(2.0);
+        let options = Newton1dOptions { bracket: Some((-3.0, 1.0)), ..Default::default() };
+        let report = newton_1d(f, 0.5, &options);
+        assert!(report.converged());
+        assert!((report.root + 1.769_292_354_238_631).abs() < 1e-12);
+        assert!(report.steps.contains(&StepKind::Bisection));
+        let (lo, hi) = report.bracket.unwrap();
+        assert!(lo <= report.root && report.root <= hi);
+    }
+
+    #[test]
+    fn stop_reasons() {
+        let no_root = newton_1d(|x| x * x + Differential::from(1.0), 0.0, &Newton1dOptions::default());
+        assert_eq!(no_root.reason, StopReason::ZeroDerivative);
+        assert!(!no_root.converged());
+
+        let options = Newton1dOptions { max_iterations: 2, ..Default::default() };
+        let report = newton_1d(|x| x.exp() - Differential::from(10.0), 0.0, &options);
+        assert_eq!((report.reason, report.iterations), (StopReason::MaxIterations, 2));
+    }
+}
@@ -0,0 +1,396 @@
+use nalgebra::{Complex, DMatrix, DVector};
+
+use crate::{jacobian, Differential};
+
+/// Options of [`continuation`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ContinuationOptions {
+    /// The first arclength step
+    pub initial_step: f64,
+
+    /// Stop when the step has to be shortened below this
+    pub min_step: f64,
+
+    /// The longest arclength step
+    pub max_step: f64,
+
+    /// Stop when the branch has this many points
+    pub max_points: usize,
+
+    /// Stop when `λ` leaves the interval `(min, max)`
+    pub parameter_range: (f64, f64),
+
+    /// Trace the branch towards increasing `λ` if `true`, towards decreasing `λ` otherwise
+    pub increasing: bool,
+
+    /// A corrected point is accepted when `‖F‖∞` is at most this
+    pub tolerance: f64,
+
+    /// Maximum number of Newton iterations of the corrector
+    pub max_corrector_iterations: usize,
+}
+
+impl Default for ContinuationOptions {
+    fn default() -> Self {
+        Self {
+            initial_step: 0.1,
+            min_step: 1e-8,
+            max_step: 1.0,
+            max_points: 1000,
+            parameter_range: (f64::NEG_INFINITY, f64::INFINITY),
+            increasing: true,
+            tolerance: 1e-10,
+            max_corrector_iterations: 10,
+        }
+    }
+}
+
+/// The stability of an equilibrium of `dx/dt = F(x, λ)`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stability {
+    /// All the eigenvalues of `∂F/∂x` have negative real parts
+    Stable,
+
+    /// An eigenvalue of `∂F/∂x` has a positive real part
+    Unstable,
+
+    /// The largest real part of the eigenvalues is zero up to rounding
+    Marginal,
+}
+
+/// A point of a solution branch
+#[derive(Debug, Clone, PartialEq)]
+pub struct BranchPoint {
+    /// The solution
+    pub x: Vec<f64>,
+
+    /// The parameter
+    pub lambda: f64,
+
+    /// The unit tangent of the branch in `(x, λ)`, oriented along the direction of the tracing
+    pub tangent: Vec<f64>,
+
+    /// The determinant of `∂F/∂x`
+    pub determinant: f64,
+
+    /// The eigenvalues of `∂F/∂x`
+    pub eigenvalues: Vec<Complex<f64>>,
+
+    /// The stability derived from the eigenvalues
+    pub stability: Stability,
+}
+
+/// What happens where the determinant of `∂F/∂x` changes sign
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpecialPointKind {
+    /// The branch turns back in `λ`
+    Fold,
+
+    /// The branch keeps its direction in `λ`, so another branch crosses it
+    BranchPoint,
+}
+
+/// A fold or a branch point between two consecutive points of a branch
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpecialPoint {
+    /// The kind of the point
+    pub kind: SpecialPointKind,
+
+    /// The index of the first point of the branch after the sign change
+    pub index: usize,
+
+    /// The solution where the determinant vanishes, located between the two points
+    pub x: Vec<f64>,
+
+    /// The parameter where the determinant vanishes
+    pub lambda: f64,
+}
+
+/// Why [`continuation`] stopped
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContinuationStopReason {
+    /// The branch reached the maximum number of points
+    MaxPoints,
+
+    /// The branch left the parameter range
+    ParameterRange,
+
+    /// The corrector failed even with the shortest step
+    MinStep,
+
+    /// The starting point could not be corrected onto the branch
+    InitialCorrection,
+
+    /// `F` or its Jacobian is not finite
+    NonFinite,
+}
+
+/// The result of [`continuation`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContinuationReport {
+    /// The points of the branch, in the order of tracing
+    pub points: Vec<BranchPoint>,
+
+    /// The folds and branch points detected along the branch
+    pub special_points: Vec<SpecialPoint>,
+
+    /// Why the tracing stopped
+    pub reason: ContinuationStopReason,
+}
+
+/// Evaluates `F` and its Jacobian with respect to `(x, λ)` at `z = (x, λ)`
+fn evaluate(f: &impl Fn(&[Differential], Differential) -> Vec<Differential>, z: &DVector<f64>) -> (DVector<f64>, DMatrix<f64>) {
+    let n = z.len() - 1;
+    let g = |z: &[Differential]| f(&z[..n], z[n]);
+    let dz: Vec<Differential> = z.iter().map(|&z| Differential::from(z)).collect();
+    let residuals = DVector::from_iterator(n, g(&dz).into_iter().map(|r| r.value));
+    (residuals, jacobian(g, z.as_slice()))
+}
+
+/// The unit null vector of the `n × (n + 1)` Jacobian, oriented like `previous`
+fn tangent(jacobian: &DMatrix<f64>, previous: &DVector<f64>) -> Option<DVector<f64>> {
+    let n = jacobian.nrows();
+    let mut augmented = jacobian.clone().insert_row(n, 0.0);
+    augmented.set_row(n, &previous.transpose());
+    let mut rhs = DVector::zeros(n + 1);
+    rhs[n] = 1.0;
+    let tangent = augmented.lu().solve(&rhs)?;
+    let norm = tangent.norm();
+    norm.is_finite().then(|| tangent / norm)
+}
+
+/// Newton's method on `F(z) = 0` and `tᵀ(z - z₀) = 0`, starting at `z₀`
+fn correct(
+    f: &impl Fn(&[Differential], Differential) -> Vec<Differential>,
+    predicted: &DVector<f64>,
+    direction: &DVector<f64>,
+    options: &ContinuationOptions,
+) -> Option<(DVector<f64>, DMatrix<f64>, usize)> {
+    let n = predicted.len() - 1;
+    let mut z = predicted.clone();
+    for iteration in 0..=options.max_corrector_iterations {
+        let (residuals, jacobian) = evaluate(f, &z);
+        if residuals.iter().chain(jacobian.iter()).any(|v| !v.is_finite()) {
+            return None;
+        }
+        if residuals.amax() <= options.tolerance {
+            return Some((z, jacobian, iteration));
+        }
+        if iteration == options.max_corrector_iterations {
+            return None;
+        }
+        let mut augmented = jacobian.insert_row(n, 0.0);
+        augmented.set_row(n, &direction.transpose());
+        let mut rhs = -residuals.insert_row(n, 0.0);
+        rhs[n] = -direction.dot(&(&z - predicted));
+        z += augmented.lu().solve(&rhs)?;
+    }
+    None
+}
+
+/// Computes the determinant, the eigenvalues and the stability of `∂F/∂x`
+fn branch_point(z: &DVector<f64>, jacobian: &DMatrix<f64>, tangent: DVector<f64>) -> BranchPoint {
+    let n = jacobian.nrows();
+    let dx = jacobian.columns(0, n).into_owned();
+    let threshold = 1e3 * f64::EPSILON * dx.norm().max(1.0);
+    let eigenvalues: Vec<Complex<f64>> = dx.complex_eigenvalues().iter().copied().collect();
+    let largest = eigenvalues.iter().map(|e| e.re).fold(f64::NEG_INFINITY, f64::max);
+    let stability = if largest < -threshold {
+        Stability::Stable
+    } else if largest > threshold {
+        Stability::Unstable
+    } else {
+        Stability::Marginal
+    };
+    BranchPoint {
+        x: z.rows(0, n).iter().copied().collect(),
+        lambda: z[n],
+        tangent: tangent.iter().copied().collect(),
+        determinant: dx.determinant(),
+        eigenvalues,
+        stability,
+    }
+}
+
+/// Locates the zero of `det ∂F/∂x` between `z` and the point corrected from `z + step t`
+///
+/// The points in between are corrected from `z + σ t` like the steps, and `σ` is found with the
+/// Illinois variant of regula falsi on the determinant.
+fn locate(
+    f: &impl Fn(&[Differential], Differential) -> Vec<Differential>,
+    z: &DVector<f64>,
+    t: &DVector<f64>,
+    determinants: (f64, f64),
+    step: f64,
+    options: &ContinuationOptions,
+) -> Option<DVector<f64>> {
+    let n = z.len() - 1;
+    let ((mut a, mut da), (mut b, mut db)) = ((0.0, determinants.0), (step, determinants.1));
+    let mut located = None;
+    for _ in 0..50 {
+        let sigma = b - db * (b - a) / (db - da);
+        let (corrected, jacobian, _) = correct(f, &(z + t * sigma), t, options)?;
+        let determinant = jacobian.columns(0, n).determinant();
+        located = Some(corrected);
+        if determinant == 0.0 || (b - a).abs() <= options.min_step {
+            break;
+        }
+        if determinant * db < 0.0 {
+            (a, da) = (b, db);
+        } else {
+            da *= 0.5;
+        }
+        (b, db) = (sigma, determinant);
+    }
+    located
+}
+
+/// Traces the solution branch of `F(x, λ) = 0` through `(x0, λ0)` with pseudo-arclength continuation
+///
+/// `f` takes the state and the parameter and returns one residual per state component. Each step
+/// predicts along the tangent of the branch and corrects with Newton's method on `F = 0`, constrained
+/// to the hyperplane orthogonal to the tangent, so the branch is followed around folds where `λ`
+/// turns back. The Jacobians come from [`jacobian`].
+///
+/// The step grows after fast corrections and is halved after failed ones. The sign changes of
+/// `det ∂F/∂x` between consecutive points are located and reported as folds or branch points, and
+/// every point carries the eigenvalues of `∂F/∂x` for the stability of the equilibria of
+/// `dx/dt = F(x, λ)`.
+pub fn continuation(
+    f: impl Fn(&[Differential], Differential) -> Vec<Differential>,
+    x0: &[f64],
+    lambda0: f64,
+    options: &ContinuationOptions,
+) -> ContinuationReport {
+    let n = x0.len();
+    let mut points = Vec::new();
+    let mut special_points = Vec::new();
+    let finish = |points, special_points, reason| ContinuationReport { points, special_points, reason };
+
+    // correct the starting point at fixed λ, then orient the tangent along λ
+    let mut direction = DVector::zeros(n + 1);
+    direction[n] = if options.increasing { 1.0 } else { -1.0 };
+    let start = DVector::from_iterator(n + 1, x0.iter().copied().chain([lambda0]));
+    let Some((mut z, jacobian, _)) = correct(&f, &start, &direction, options) else {
+        return finish(points, special_points, ContinuationStopReason::InitialCorrection);
+    };
+    let Some(mut t) = tangent(&jacobian, &direction) else {
+        return finish(points, special_points, ContinuationStopReason::NonFinite);
+    };
+    points.push(branch_point(&z, &jacobian, t.clone()));
+    let mut step = options.initial_step.clamp(options.min_step, options.max_step);
+
+    let reason = loop {
+        if points.len() >= options.max_points {
+            break ContinuationStopReason::MaxPoints;
+        }
+        let predicted = &z + &t * step;
+        let Some((corrected, jacobian, iterations)) = correct(&f, &predicted, &t, options) else {
+            step *= 0.5;
+            if step < options.min_step {
+                break ContinuationStopReason::MinStep;
+            }
+            continue;
+        };
+        let Some(next) = tangent(&jacobian, &t) else {
+            break ContinuationStopReason::NonFinite;
+        };
+        let lambda = corrected[n];
+        if lambda <= options.parameter_range.0 || lambda >= options.parameter_range.1 {
+            break ContinuationStopReason::ParameterRange;
+        }
+
+        let point = branch_point(&corrected, &jacobian, next.clone());
+        let previous = points.last().unwrap();
+        if previous.determinant * point.determinant < 0.0 {
+            let located = locate(&f, &z, &t, (previous.determinant, point.determinant), step, options).unwrap_or_else(|| {
+                let s = previous.determinant / (previous.determinant - point.determinant);
+                &z + (&corrected - &z) * s
+            });
+            special_points.push(SpecialPoint {
+                kind: if t[n] * next[n] < 0.0 { SpecialPointKind::Fold } else { SpecialPointKind::BranchPoint },
+                index: points.len(),
+                x: located.rows(0, n).iter().copied().collect(),
+                lambda: located[n],
+            });
+        }
+        points.push(point);
+        z = corrected;
+        t = next;
+        if iterations <= 2 {
+            step = (step * 1.5).min(options.max_step);
+        }
+    };
+
+    finish(points, special_points, reason)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn saddle_node() {
+        // dx/dt = λ - x² has a fold at the origin, with stable equilibria for x > 0
+        let options = ContinuationOptions {
+            increasing: false,
+            parameter_range: (-1.0, 2.0),
+            ..Default::default()
+        };
+        let report = continuation(|x, lambda| vec![lambda - x[0] * x[0]], &[-1.0], 1.0, &options);
+        assert_eq!(report.reason, ContinuationStopReason::ParameterRange);
+        assert_eq!(report.special_points.len(), 1);
+        let fold = &report.special_points[0];
+        assert_eq!(fold.kind, SpecialPointKind::Fold);
+        assert!(fold.lambda.abs() < 1e-12 && fold.x[0].abs() < 1e-6, "{:?}", fold);
+        for point in &report.points {
+            assert!((point.lambda - point.x[0] * point.x[0]).abs() <= 1e-10);
+            let expected = if point.x[0] > 0.0 { Stability::Stable } else { Stability::Unstable };
+            assert_eq!(point.stability, expected);
+        }
+        assert!(report.points.last().unwrap().x[0] > 1.0);
+    }
+
+    #[test]
+    fn hysteresis() {
+        // the S-shaped branch of dx/dt = λ + x - x³ folds back twice at λ = ±2 / √27
+        let options = ContinuationOptions {
+            parameter_range: (-2.0, 2.0),
+            ..Default::default()
+        };
+        let f = |x: &[Differential], lambda: Differential| vec![lambda + x[0] - x[0] * x[0] * x[0]];
+        let report = continuation(f, &[-1.5], -2.0 + 1e-3, &options);
+        assert_eq!(report.reason, ContinuationStopReason::ParameterRange);
+        let folds: Vec<f64> = report.special_points.iter().map(|p| p.lambda).collect();
+        assert!(report.special_points.iter().all(|p| p.kind == SpecialPointKind::Fold));
+        let expected = 2.0 / 27f64.sqrt();
+        assert_eq!(folds.len(), 2);
+        assert!((folds[0] - expected).abs() < 1e-10 && (folds[1] + expected).abs() < 1e-10, "{:?}", folds);
+        // only the middle part of the branch, between the folds, is unstable
+        let middle = &report.points[report.special_points[0].index..report.special_points[1].index];
+        assert!(middle.iter().all(|p| p.stability == Stability::Unstable));
+        assert_eq!(report.points.last().unwrap().stability, Stability::Stable);
+    }
+
+    #[test]
+    fn pitchfork_and_limits() {
+        // the trivial branch of dx/dt = λx - x³ loses its stability at λ = 0
+        let f = |x: &[Differential], lambda: Differential| vec![lambda * x[0] - x[0] * x[0] * x[0]];
+        let options = ContinuationOptions {
+            parameter_range: (-1.0, 1.0),
+            ..Default::default()
+        };
+        let report = continuation(f, &[0.0], -0.95, &options);
+        assert_eq!(report.special_points.len(), 1);
+        assert_eq!(report.special_points[0].kind, SpecialPointKind::BranchPoint);
+        assert!(report.special_points[0].lambda.abs() < 1e-12);
+        assert_eq!(report.points[0].stability, Stability::Stable);
+        assert_eq!(report.points.last().unwrap().stability, Stability::Unstable);
+
+        let short = ContinuationOptions { max_points: 3, ..options };
+        assert_eq!(continuation(f, &[0.0], -0.95, &short).points.len(), 3);
+        let unsolvable = continuation(|x, lambda| vec![x[0] * x[0] + lambda * lambda + Differential::from(1.0)], &[0.0], 0.0, &options);
+        assert_eq!(unsolvable.reason, ContinuationStopReason::InitialCorrection);
+        assert!(unsolvable.points.is_empty());
+    }
+}
@@ -0,0 +1,306 @@
+use nalgebra::{DMatrix, DVector};
+
+use crate::{jacobian, Differential};
+
+/// The factorization used to solve for the Newton step
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LinearSolver {
+    /// LU decomposition with partial pivoting, for square systems
+    #[default]
+    Lu,
+
+    /// QR decomposition, which also solves overdetermined systems in the least-squares sense
+    Qr,
+}
+
+/// Options of [`newton`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NewtonOptions {
+    /// Stop when the largest absolute residual is at most this
+    pub tolerance: f64,
+
+    /// Stagnation is reported when a step is at most `step_tolerance * (1 + ‖x‖∞)`
+    pub step_tolerance: f64,
+
+    /// Maximum number of iterations
+    pub max_iterations: usize,
+
+    /// The factorization of the Jacobian
+    pub linear_solver: LinearSolver,
+
+    /// Maximum number of step halvings of the backtracking line search
+    pub max_backtracks: usize,
+
+    /// Update the Jacobian with Broyden's method instead of recomputing it every iteration
+    ///
+    /// The exact Jacobian is recomputed whenever the updated one fails to produce a descent step.
+    pub broyden: bool,
+}
+
+impl Default for NewtonOptions {
+    fn default() -> Self {
+        Self {
+            tolerance: 1e-10,
+            step_tolerance: 1e-14,
+            max_iterations: 100,
+            linear_solver: LinearSolver::Lu,
+            max_backtracks: 30,
+            broyden: false,
+        }
+    }
+}
+
+/// Why [`newton`] stopped
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NewtonStopReason {
+    /// The residuals reached the tolerance
+    Converged,
+
+    /// The iteration made no progress: the line search failed or the step became negligible
+    Stagnation,
+
+    /// The exact Jacobian is singular (or rank-deficient) at the current point
+    SingularJacobian,
+
+    /// The system has fewer equations than unknowns, so its roots are not isolated
+    Underdetermined,
+
+    /// The maximum number of iterations was reached
+    MaxIterations,
+
+    /// The residuals are not finite
+    NonFinite,
+}
+
+/// The result of [`newton`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct NewtonReport {
+    /// The last iterate
+    pub x: Vec<f64>,
+
+    /// The residuals `F(x)`
+    pub residuals: Vec<f64>,
+
+    /// The number of iterations
+    pub iterations: usize,
+
+    /// The number of evaluations of `F` without derivatives
+    pub function_evaluations: usize,
+
+    /// The number of exact Jacobian evaluations
+    pub jacobian_evaluations: usize,
+
+    /// The Euclidean norm of the residuals of the initial point and of every iterate
+    pub residual_history: Vec<f64>,
+
+    /// Why the iteration stopped
+    pub reason: NewtonStopReason,
+}
+
+impl NewtonReport {
+    /// Returns `true` if the residuals reached the tolerance
+    pub fn converged(&self) -> bool {
+        self.reason == NewtonStopReason::Converged
+    }
+}
+
+/// Counts the evaluations of the system
+struct System<F> {
+    f: F,
+    function_evaluations: usize,
+    jacobian_evaluations: usize,
+}
+
+impl<F> System<F>
+where
+    F: Fn(&[Differential]) -> Vec<Differential>,
+{
+    fn residuals(&mut self, x: &DVector<f64>) -> DVector<f64> {
+        self.function_evaluations += 1;
+        let params: Vec<Differential> = x.iter().map(|x| (*x).into()).collect();
+        let outputs = (self.f)(&params);
+        DVector::from_iterator(outputs.len(), outputs.iter().map(|y| y.value))
+    }
+
+    fn jacobian(&mut self, x: &DVector<f64>) -> DMatrix<f64> {
+        self.jacobian_evaluations += 1;
+        jacobian(&self.f, x.as_slice())
+    }
+}
+
+/// Solves `J dx = -r`, returning `None` if `J` is singular
+fn newton_step(jacobian: &DMatrix<f64>, residuals: &DVector<f64>, solver: LinearSolver) -> Option<DVector<f64>> {
+    let rhs = -residuals;
+    if solver == LinearSolver::Lu && jacobian.is_square() {
+        return jacobian.clone().lu().solve(&rhs).filter(|dx| dx.iter().all(|x| x.is_finite()));
+    }
+    let (q, r) = jacobian.clone().qr().unpack();
+    let scale = r.diagonal().amax();
+    let threshold = f64::EPSILON * scale * jacobian.nrows() as f64;
+    if scale == 0.0 || r.diagonal().iter().any(|d| d.abs() <= threshold) {
+        return None;
+    }
+    r.solve_upper_triangular(&(q.transpose() * rhs))
+}
+
+/// Solves the nonlinear system `F(x) = 0` with a globalized Newton method starting at `x0`
+///
+/// The Jacobian is obtained by forward differentiation with [`jacobian`], optionally replaced by
+/// Broyden updates between recomputations. Each step is globalized by a backtracking line search
+/// on `½‖F(x)‖²`. Overdetermined systems need [`LinearSolver::Qr`], underdetermined ones are
+/// rejected with [`NewtonStopReason::Underdetermined`].
+pub fn newton(f: impl Fn(&[Differential]) -> Vec<Differential>, x0: &[f64], options: &NewtonOptions) -> NewtonReport {
+    let mut system = System { f, function_evaluations: 0, jacobian_evaluations: 0 };
+    let mut x = DVector::from_column_slice(x0);
+    let mut residuals = system.residuals(&x);
+    let mut residual_history = vec![residuals.norm()];
+    let mut jacobian: Option<DMatrix<f64>> = None;
+    let mut exact = false;
+    let mut iterations = 0;
+
+    let reason = loop {
+        if residuals.len() < x.len() {
+            break NewtonStopReason::Underdetermined;
+        }
+        if residuals.iter().any(|r| !r.is_finite()) {
+            break NewtonStopReason::NonFinite;
+        }
+        if residuals.amax() <= options.tolerance {
+            break NewtonStopReason::Converged;
+        }
+        if iterations >= options.max_iterations {
+            break NewtonStopReason::MaxIterations;
+        }
+
+        let j = match jacobian.take() {
+            Some(j) if options.broyden => j,
+            _ => {
+                exact = true;
+                system.jacobian(&x)
+            }
+        };
+        let Some(dx) = newton_step(&j, &residuals, options.linear_solver) else {
+            if exact {
+                break NewtonStopReason::SingularJacobian;
+            }
+            // the Broyden update degenerated, retry with the exact Jacobian
+            continue;
+        };
+
+        // Armijo backtracking on the merit function ½‖F‖²
+        let merit = 0.5 * residuals.norm_squared();
+        let mut alpha = 1.0;
+        let mut accepted = None;
+        for _ in 0..=options.max_backtracks {
+            let candidate = &x + &dx * alpha;
+            let candidate_residuals = system.residuals(&candidate);
+            let candidate_merit = 0.5 * candidate_residuals.norm_squared();
+            if candidate_merit.is_finite() && candidate_merit <= (1.0 - 2e-4 * alpha) * merit {
+                accepted = Some((candidate, candidate_residuals));
+                break;
+            }
+            alpha *= 0.5;
+        }
+        let Some((next, next_residuals)) = accepted else {
+            if exact {
+                break NewtonStopReason::Stagnation;
+            }
+            continue;
+        };
+
+        iterations += 1;
+        let step = &next - &x;
+        let step_norm = step.amax();
+        if options.broyden {
+            // J ← J + (Δr - J Δx) Δxᵀ / (Δxᵀ Δx)
+            let denominator = step.norm_squared();
+            let mut j = j;
+            if denominator > 0.0 {
+                let correction = (&next_residuals - &residuals - &j * &step) / denominator;
+                j += correction * step.transpose();
+            }
+            jacobian = Some(j);
+            exact = false;
+        }
+        x = next;
+        residuals = next_residuals;
+        residual_history.push(residuals.norm());
+
+        if residuals.amax() > options.tolerance && step_norm <= options.step_tolerance * (1.0 + x.amax()) {
+            break NewtonStopReason::Stagnation;
+        }
+    };
+
+    NewtonReport {
+        x: x.iter().copied().collect(),
+        residuals: residuals.iter().copied().collect(),
+        iterations,
+        function_evaluations: system.function_evaluations,
+        jacobian_evaluations: system.jacobian_evaluations,
+        residual_history,
+        reason,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use num_traits::Float;
+
+    use super::*;
+
+    fn rosenbrock_system(x: &[Differential]) -> Vec<Differential> {
+        vec![(Differential::from(1.0) - x[0]) * 10.0, (x[1] - x[0] * x[0]) * 10.0]
+    }
+
+    #[test]
+    fn solves_square_system() {
+        for linear_solver in [LinearSolver::Lu, LinearSolver::Qr] {
+            let options = NewtonOptions { linear_solver, ..Default::default() };
+            let report = newton(rosenbrock_system, &[-1.2, 1.0], &options);
+            assert!(report.converged());
+            assert!((report.x[0] - 1.0).abs() < 1e-10 && (report.x[1] - 1.0).abs() < 1e-10);
+            assert_eq!(report.residual_history.len(), report.iterations + 1);
+        }
+    }
+
+    #[test]
+    fn broyden_saves_jacobians() {
+        let f = |x: &[Differential]| vec![x[0] * x[0] + x[1] * x[1] - Differential::from(4.0), x[0].exp() + x[1] - Differential::from(1.0)];
+        let exact = newton(f, &[1.0, -1.0], &NewtonOptions::default());
+        let broyden = newton(f, &[1.0, -1.0], &NewtonOptions { broyden: true, ..Default::default() });
+        assert!(exact.converged() && broyden.converged());
+        assert!((exact.x[0] - broyden.x[0]).abs() < 1e-8);
+        assert!(broyden.jacobian_evaluations < exact.jacobian_evaluations);
+    }
+
+    #[test]
+    fn failure_reasons() {
+        // the Jacobian vanishes at the origin
+        let singular = newton(|x| vec![x[0] * x[0] + Differential::from(1.0), x[1] * x[1] + Differential::from(1.0)], &[0.0, 0.0], &NewtonOptions::default());
+        assert_eq!(singular.reason, NewtonStopReason::SingularJacobian);
+
+        // x² + 1 has no real root, so the line search stalls at its minimum
+        let stagnation = newton(|x| vec![x[0] * x[0] + Differential::from(1.0)], &[0.5], &NewtonOptions::default());
+        assert_eq!(stagnation.reason, NewtonStopReason::Stagnation);
+        assert!(!stagnation.converged());
+
+        let options = NewtonOptions { max_iterations: 1, ..Default::default() };
+        assert_eq!(newton(rosenbrock_system, &[-1.2, 1.0], &options).reason, NewtonStopReason::MaxIterations);
+
+        // a circle has a continuum of roots
+        let circle = |x: &[Differential]| vec![x[0] * x[0] + x[1] * x[1] - Differential::from(1.0)];
+        for linear_solver in [LinearSolver::Lu, LinearSolver::Qr] {
+            let underdetermined = newton(circle, &[2.0, 0.0], &NewtonOptions { linear_solver, ..Default::default() });
+            assert_eq!(underdetermined.reason, NewtonStopReason::Underdetermined);
+            assert_eq!((underdetermined.iterations, underdetermined.jacobian_evaluations), (0, 0));
+        }
+    }
+
+    #[test]
+    fn overdetermined_consistent_system() {
+        let f = |x: &[Differential]| vec![x[0] - Differential::from(2.0), x[0] * x[0] - Differential::from(4.0)];
+        let report = newton(f, &[1.0], &NewtonOptions { linear_solver: LinearSolver::Qr, ..Default::default() });
+        assert!(report.converged());
+        assert!((report.x[0] - 2.0).abs() < 1e-10);
+    }
+}
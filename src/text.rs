@@ -0,0 +1,409 @@
+/*!
+Textual representation of differentials.
+
+A differential is written as `value + derivativeε`, for example `1.5 + 2ε` or `1.5 - 2ε`.
+Gradients use a bracketed list, `1.5 + [1, -2]ε`, and nested differentials are wrapped in
+parentheses, `1 + (2 + 3ε)ε`. When parsing, `eps` is accepted in place of `ε` and a plain number
+is read as a differential with zero derivative.
+*/
+
+use core::fmt;
+use core::str::FromStr;
+
+use num_traits::{Num, Zero};
+
+#[cfg(any(feature = "std", feature = "libm"))]
+use crate::interval::Interval;
+use crate::Differential;
+
+/// The symbol written after the derivative
+pub const EPSILON_SYMBOL: &str = "ε";
+
+/// Error returned when parsing a [`Differential`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParseDifferentialError {
+    /// The string is empty
+    Empty,
+
+    /// The value part could not be parsed
+    InvalidValue,
+
+    /// The derivative part could not be parsed
+    InvalidDerivative,
+}
+
+impl fmt::Display for ParseDifferentialError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseDifferentialError::Empty => write!(f, "cannot parse differential from empty string"),
+            ParseDifferentialError::InvalidValue => write!(f, "invalid differential value"),
+            ParseDifferentialError::InvalidDerivative => write!(f, "invalid differential derivative"),
+        }
+    }
+}
+
+impl core::error::Error for ParseDifferentialError {}
+
+/// The formatting trait used to write numbers
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NumberStyle {
+    /// [`fmt::Display`]
+    Display,
+
+    /// [`fmt::LowerExp`]
+    LowerExp,
+
+    /// [`fmt::UpperExp`]
+    UpperExp,
+}
+
+impl NumberStyle {
+    /// Writes `x` with this style, honoring the flags of `f`
+    pub fn fmt<T>(self, x: &T, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    where
+        T: fmt::Display + fmt::LowerExp + fmt::UpperExp + ?Sized,
+    {
+        match self {
+            NumberStyle::Display => fmt::Display::fmt(x, f),
+            NumberStyle::LowerExp => fmt::LowerExp::fmt(x, f),
+            NumberStyle::UpperExp => fmt::UpperExp::fmt(x, f),
+        }
+    }
+}
+
+/// A derivative type that can be written and read in the textual form of a [`Differential`]
+pub trait TangentText: Sized {
+    /// Writes the derivative part including the leading sign, e.g. ` + 2ε`
+    fn fmt_tangent(&self, f: &mut fmt::Formatter<'_>, style: NumberStyle) -> fmt::Result;
+
+    /// Parses the derivative from the text between the sign and the `ε` symbol
+    ///
+    /// `negative` is `true` if the derivative is preceded by `-` instead of `+`.
+    fn parse_tangent(s: &str, negative: bool, radix: u32) -> Result<Self, ParseDifferentialError>;
+}
+
+macro_rules! tangent_text_signed {
+    ($($t:ty),*) => {
+        $(
+            impl TangentText for $t {
+                fn fmt_tangent(&self, f: &mut fmt::Formatter<'_>, style: NumberStyle) -> fmt::Result {
+                    if self.is_sign_negative() {
+                        write!(f, " - ")?;
+                        style.fmt(&-*self, f)?;
+                    } else {
+                        write!(f, " + ")?;
+                        style.fmt(self, f)?;
+                    }
+                    write!(f, "{}", EPSILON_SYMBOL)
+                }
+
+                fn parse_tangent(s: &str, negative: bool, radix: u32) -> Result<Self, ParseDifferentialError> {
+                    let x = <$t as Num>::from_str_radix(s, radix).map_err(|_| ParseDifferentialError::InvalidDerivative)?;
+                    Ok(if negative { -x } else { x })
+                }
+            }
+        )*
+    };
+}
+
+tangent_text_signed!(f32, f64);
+
+macro_rules! tangent_text_integer {
+    ($($t:ty: $unsigned:ty),*) => {
+        $(
+            impl TangentText for $t {
+                fn fmt_tangent(&self, f: &mut fmt::Formatter<'_>, style: NumberStyle) -> fmt::Result {
+                    if *self < 0 {
+                        write!(f, " - ")?;
+                        // unsigned_abs avoids the overflow of `-MIN`
+                        style.fmt(&self.unsigned_abs(), f)?;
+                    } else {
+                        write!(f, " + ")?;
+                        style.fmt(self, f)?;
+                    }
+                    write!(f, "{}", EPSILON_SYMBOL)
+                }
+
+                fn parse_tangent(s: &str, negative: bool, radix: u32) -> Result<Self, ParseDifferentialError> {
+                    // parse the magnitude as unsigned so that `MIN` can be read back
+                    let magnitude = <$unsigned as Num>::from_str_radix(s, radix).map_err(|_| ParseDifferentialError::InvalidDerivative)?;
+                    let x = if negative { (0 as $t).checked_sub_unsigned(magnitude) } else { (0 as $t).checked_add_unsigned(magnitude) };
+                    x.ok_or(ParseDifferentialError::InvalidDerivative)
+                }
+            }
+        )*
+    };
+}
+
+tangent_text_integer!(i8: u8, i16: u16, i32: u32, i64: u64, i128: u128, isize: usize);
+
+macro_rules! tangent_text_unsigned {
+    ($($t:ty),*) => {
+        $(
+            impl TangentText for $t {
+                fn fmt_tangent(&self, f: &mut fmt::Formatter<'_>, style: NumberStyle) -> fmt::Result {
+                    write!(f, " + ")?;
+                    style.fmt(self, f)?;
+                    write!(f, "{}", EPSILON_SYMBOL)
+                }
+
+                fn parse_tangent(s: &str, negative: bool, radix: u32) -> Result<Self, ParseDifferentialError> {
+                    match <$t as Num>::from_str_radix(s, radix) {
+                        Ok(x) if !negative || x == 0 => Ok(x),
+                        _ => Err(ParseDifferentialError::InvalidDerivative),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+tangent_text_unsigned!(u8, u16, u32, u64, u128, usize);
+
+#[cfg(any(feature = "std", feature = "libm"))]
+impl TangentText for Interval {
+    fn fmt_tangent(&self, f: &mut fmt::Formatter<'_>, style: NumberStyle) -> fmt::Result {
+        write!(f, " + ")?;
+        style.fmt(self, f)?;
+        write!(f, "{}", EPSILON_SYMBOL)
+    }
+
+    fn parse_tangent(s: &str, negative: bool, radix: u32) -> Result<Self, ParseDifferentialError> {
+        let x = Interval::from_str_radix(s, radix).map_err(|_| ParseDifferentialError::InvalidDerivative)?;
+        Ok(if negative { -x } else { x })
+    }
+}
+
+impl<T, D> TangentText for Differential<T, D>
+where
+    T: Num + fmt::Display + fmt::LowerExp + fmt::UpperExp,
+    D: Zero + TangentText,
+{
+    fn fmt_tangent(&self, f: &mut fmt::Formatter<'_>, style: NumberStyle) -> fmt::Result {
+        write!(f, " + (")?;
+        self.fmt_styled(f, style)?;
+        write!(f, "){}", EPSILON_SYMBOL)
+    }
+
+    fn parse_tangent(s: &str, negative: bool, radix: u32) -> Result<Self, ParseDifferentialError> {
+        if negative {
+            return Err(ParseDifferentialError::InvalidDerivative);
+        }
+        let inner = s
+            .strip_prefix('(')
+            .and_then(|s| s.strip_suffix(')'))
+            .ok_or(ParseDifferentialError::InvalidDerivative)?;
+        Self::from_str_radix_with_tangent(inner, radix).map_err(|_| ParseDifferentialError::InvalidDerivative)
+    }
+}
+
+impl<T, D> Differential<T, D> {
+    /// Writes the differential with the given number style
+    fn fmt_styled(&self, f: &mut fmt::Formatter<'_>, style: NumberStyle) -> fmt::Result
+    where
+        T: fmt::Display + fmt::LowerExp + fmt::UpperExp,
+        D: TangentText,
+    {
+        style.fmt(&self.value, f)?;
+        self.derivative.fmt_tangent(f, style)
+    }
+
+    /// Parses a differential together with its derivative, written in the given radix
+    ///
+    /// This is [`str::parse`] for other radixes than 10, for example `ff - aε` in radix 16.
+    /// [`Num::from_str_radix`] reads only a plain value.
+    pub fn from_str_radix_with_tangent(s: &str, radix: u32) -> Result<Self, ParseDifferentialError>
+    where
+        T: Num,
+        D: Zero + TangentText,
+    {
+        let s = s.trim();
+        if s.is_empty() {
+            return Err(ParseDifferentialError::Empty);
+        }
+        let parse_value = |s: &str| T::from_str_radix(s.trim(), radix).map_err(|_| ParseDifferentialError::InvalidValue);
+
+        let Some(body) = s.strip_suffix(EPSILON_SYMBOL).or_else(|| s.strip_suffix("eps")) else {
+            return Ok(Self::new(parse_value(s)?, D::zero()));
+        };
+        let body = body.trim_end();
+
+        let (value, negative, derivative) = split_tangent(body, radix).ok_or(ParseDifferentialError::InvalidDerivative)?;
+        Ok(Self::new(parse_value(value)?, D::parse_tangent(derivative, negative, radix)?))
+    }
+
+    /// Parses a plain value written in the given radix as a differential with zero derivative
+    ///
+    /// Unlike [`from_str_radix_with_tangent`](Self::from_str_radix_with_tangent), this does not need [`TangentText`], so it works for every
+    /// derivative type. A written derivative is rejected.
+    pub(crate) fn parse_value(s: &str, radix: u32) -> Result<Self, ParseDifferentialError>
+    where
+        T: Num,
+        D: Zero,
+    {
+        let s = s.trim();
+        if s.is_empty() {
+            return Err(ParseDifferentialError::Empty);
+        }
+        if s.ends_with(EPSILON_SYMBOL) || s.ends_with("eps") {
+            return Err(ParseDifferentialError::InvalidDerivative);
+        }
+        let value = T::from_str_radix(s, radix).map_err(|_| ParseDifferentialError::InvalidValue)?;
+        Ok(Self::new(value, D::zero()))
+    }
+}
+
+/// Splits `value ± derivative` (without the `ε` symbol) into the value, the sign and the derivative
+fn split_tangent(body: &str, radix: u32) -> Option<(&str, bool, &str)> {
+    let bytes = body.as_bytes();
+    let sign_index = match bytes.last()? {
+        closing @ (b']' | b')') => {
+            // bracketed derivative, find the matching opening bracket
+            let opening = if *closing == b']' { b'[' } else { b'(' };
+            let mut depth = 0usize;
+            let mut start = None;
+            for (i, byte) in bytes.iter().enumerate().rev() {
+                if *byte == *closing {
+                    depth += 1;
+                } else if *byte == opening {
+                    depth -= 1;
+                    if depth == 0 {
+                        start = Some(i);
+                        break;
+                    }
+                }
+            }
+            let start = start?;
+            let sign_index = body[..start].trim_end().len().checked_sub(1)?;
+            if bytes[sign_index] != b'+' {
+                return None;
+            }
+            return Some((&body[..sign_index], false, &body[start..]));
+        }
+        _ => {
+            // scalar derivative, find the last sign that is not part of an exponent
+            (1..bytes.len()).rev().find(|&i| {
+                let exponent = radix <= 10 && matches!(bytes[i - 1], b'e' | b'E');
+                matches!(bytes[i], b'+' | b'-') && !exponent
+            })?
+        }
+    };
+    Some((&body[..sign_index], bytes[sign_index] == b'-', body[sign_index + 1..].trim()))
+}
+
+impl<T, D> fmt::Display for Differential<T, D>
+where
+    T: fmt::Display + fmt::LowerExp + fmt::UpperExp,
+    D: TangentText,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.fmt_styled(f, NumberStyle::Display)
+    }
+}
+
+impl<T, D> fmt::LowerExp for Differential<T, D>
+where
+    T: fmt::Display + fmt::LowerExp + fmt::UpperExp,
+    D: TangentText,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.fmt_styled(f, NumberStyle::LowerExp)
+    }
+}
+
+impl<T, D> fmt::UpperExp for Differential<T, D>
+where
+    T: fmt::Display + fmt::LowerExp + fmt::UpperExp,
+    D: TangentText,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.fmt_styled(f, NumberStyle::UpperExp)
+    }
+}
+
+impl<T, D> FromStr for Differential<T, D>
+where
+    T: Num,
+    D: Zero + TangentText,
+{
+    type Err = ParseDifferentialError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::from_str_radix_with_tangent(s, 10)
+    }
+}
+
+#[cfg(all(test, feature = "nalgebra"))]
+mod tests {
+    use nalgebra::{Matrix2, Vector2, Vector3};
+
+    use super::*;
+
+    #[test]
+    fn display() {
+        assert_eq!(Differential::new(1.5, 2.0).to_string(), "1.5 + 2ε");
+        assert_eq!(Differential::new(1.5, -2.0).to_string(), "1.5 - 2ε");
+        assert_eq!(format!("{:.2}", Differential::new(1.5, 2.0)), "1.50 + 2.00ε");
+        assert_eq!(format!("{:e}", Differential::new(1500.0, 0.25)), "1.5e3 + 2.5e-1ε");
+        assert_eq!(format!("{:.1E}", Differential::new(1500.0, -0.25)), "1.5E3 - 2.5E-1ε");
+        assert_eq!(Differential::new(1, i32::MIN).to_string(), "1 - 2147483648ε");
+        assert_eq!("1 - 2147483648ε".parse::<Differential<i32>>().map(|d| d.derivative), Ok(i32::MIN));
+        assert_eq!(Differential::new(1.0, Vector2::new(1.0, -2.0)).to_string(), "1 + [1, -2]ε");
+        assert_eq!(Differential::new(1.0, Differential::new(2.0, 3.0)).to_string(), "1 + (2 + 3ε)ε");
+    }
+
+    #[test]
+    fn parse() {
+        assert_eq!("1.5 + 2ε".parse(), Ok(Differential::new(1.5, 2.0)));
+        assert_eq!("1.5-2eps".parse::<Differential>().unwrap().derivative, -2.0);
+        assert_eq!("-1e-3 - 2.5E+2ε".parse::<Differential>().map(|d| (d.value, d.derivative)), Ok((-1e-3, -250.0)));
+        assert_eq!("3".parse::<Differential>().map(|d| d.derivative), Ok(0.0));
+        assert_eq!(Differential::<i32>::from_str_radix_with_tangent("ff - aε", 16).map(|d| (d.value, d.derivative)), Ok((255, -10)));
+
+        let gradient: Differential<f64, Vector3<f64>> = "1 + [1, -2, 3e2]ε".parse().unwrap();
+        assert_eq!(gradient.derivative, Vector3::new(1.0, -2.0, 300.0));
+        assert!("1 + [1, 2, 3]ε".parse::<Differential<f64, Vector2<f64>>>().is_err());
+
+        let nested: Differential<f64, Differential<f64>> = "1 + (2 - 3ε)ε".parse().unwrap();
+        assert_eq!((nested.derivative.value, nested.derivative.derivative), (2.0, -3.0));
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert_eq!("".parse::<Differential>(), Err(ParseDifferentialError::Empty));
+        assert_eq!("x + 2ε".parse::<Differential>(), Err(ParseDifferentialError::InvalidValue));
+        assert_eq!("1 + yε".parse::<Differential>(), Err(ParseDifferentialError::InvalidDerivative));
+        assert_eq!("1 2ε".parse::<Differential>(), Err(ParseDifferentialError::InvalidDerivative));
+    }
+
+    #[test]
+    fn from_str_radix_without_tangent_text() {
+        // matrices have no textual tangent form, but are still valid derivatives of a `Num`
+        type MatrixTangent = Differential<f64, Matrix2<f64>>;
+        assert_eq!(<MatrixTangent as Num>::from_str_radix(" 1.5 ", 10), Ok(Differential::new(1.5, Matrix2::zeros())));
+        assert_eq!(<MatrixTangent as Num>::from_str_radix("1 + 2ε", 10), Err(ParseDifferentialError::InvalidDerivative));
+        assert_eq!(<Differential<i32> as Num>::from_str_radix("ff", 16), Ok(Differential::new(255, 0)));
+        assert_eq!(<Differential as Num>::from_str_radix("", 10), Err(ParseDifferentialError::Empty));
+    }
+
+    #[test]
+    fn hexadecimal_round_trip() {
+        let x = Differential::new(0x1f3i64, -0x2ai64);
+        let text = format!("{:x} - {:x}ε", x.value, -x.derivative);
+        let parsed = Differential::<i64>::from_str_radix_with_tangent(&text, 16).unwrap();
+        assert_eq!(parsed, x);
+        assert_eq!(format!("{:x} - {:x}ε", parsed.value, -parsed.derivative), text);
+
+        assert_eq!(Differential::<f64>::from_str_radix_with_tangent("ff.8 + 0.4ε", 16), Ok(Differential::new(255.5, 0.25)));
+        let gradient = Differential::<i32, Vector2<i32>>::from_str_radix_with_tangent("1f + [a, -b]ε", 16).unwrap();
+        assert_eq!(gradient, Differential::new(31, Vector2::new(10, -11)));
+        let nested = Differential::<i32, Differential<i32>>::from_str_radix_with_tangent("10 + (ff - 1ε)ε", 16).unwrap();
+        assert_eq!(nested, Differential::new(16, Differential::new(255, -1)));
+    }
+
+    #[test]
+    fn round_trip() {
+        let x = Differential::new(0.1, -1.0 / 3.0);
+        assert_eq!(x.to_string().parse::<Differential>().map(|d| d.derivative), Ok(x.derivative));
+    }
+}